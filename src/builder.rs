@@ -0,0 +1,1113 @@
+//! Build widget trees from a text UI description.
+//!
+//! ```text
+//! // Comments start with "//"
+//! VBox(id: "main", spacing: 4) {
+//!     Label(text: "Name", halign: Start)
+//!     MarginBox(top: 5, bottom: 5, left: 5, right: 5, pack_expand: true) {
+//!         Button(id: "ok", label: "OK", on_clicked: "ok_clicked")
+//!     }
+//! }
+//! ```
+//!
+//! A widget is a type name followed by optional properties in parentheses and
+//! optional children in braces. Values are strings, numbers, `true`/`false`,
+//! enum names such as `Center`, or lists such as `[0, 0, 100, 20]`.
+//!
+//! Every widget accepts `id`, `halign`, `valign`, `hexpand`, `vexpand`,
+//! `min_width`, `min_height`, `max_width`, `max_height`, `fixed_size`, `aspect_ratio`,
+//! `visible` and `sensitive`.
+//! Some properties of a child are read by its container:
+//! `pack_expand`, `pack_fill`, `pack_padding` and `pack_end` in VBox/HBox,
+//! `tab` in Notebook, `name` in Stack, `shrink` in HPaned/VPaned,
+//! `offset` in Overlay and `rect` in Layout, which is relative to the Layout.
+//! RadioButtons with the same `group` name share a group, which
+//! `Builder::get_radio_group` returns.
+//!
+//! Callback properties (`on_clicked`, ...) name a handler which is connected
+//! later with `Builder::connect`.
+//!
+//! A `Builder` is made by `Builder::from_file` or by parsing a string with `str::parse`.
+
+use std::rc::Rc;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::iter::Peekable;
+use std::path::Path;
+use std::str::{Chars, FromStr};
+use std::time::Duration;
+
+use prelude::*;
+use enums::{Align, CheckState, ScrollPolicy, StackTransition};
+use widgets::*;
+
+/// Arguments passed to the handlers connected by name
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Signal {
+    /// on_clicked
+    Clicked,
+    /// on_value_changed
+    ValueChanged(f64),
+    /// on_page_changed
+    PageChanged(usize),
+    /// on_position_changed
+    PositionChanged(u32),
+    /// on_expanded_changed
+    ExpandedChanged(bool),
+    /// on_toggled
+    Toggled(bool),
+    /// on_selection_changed of a RadioButton's group, with the selected index
+    SelectionChanged(usize),
+    /// on_changed. The new text can be got from the widget.
+    Changed,
+    /// on_activate
+    Activated,
+}
+
+type Handlers = Rc<RefCell<HashMap<String, Rc<Fn(Signal) + 'static>>>>;
+
+/// Widget tree built from a UI description
+pub struct Builder {
+    root: AnyWidget,
+    ids: HashMap<String, AnyWidget>,
+    groups: HashMap<String, Rc<RadioGroup>>,
+    handlers: Handlers,
+    handler_names: Vec<String>,
+}
+
+impl FromStr for Builder {
+    type Err = BuildError;
+
+    /// Parse a UI description and build the widgets
+    fn from_str(src: &str) -> Result<Builder, BuildError> {
+        let node = try!(Parser::new(src).and_then(|mut p| p.parse_document()));
+
+        let mut context = BuildContext{
+            ids: HashMap::new(),
+            groups: HashMap::new(),
+            handlers: Rc::new(RefCell::new(HashMap::new())),
+            handler_names: Vec::new(),
+        };
+        let root = try!(context.build(&node));
+        try!(node.check_unused());
+
+        Ok(Builder{
+            root: root,
+            ids: context.ids,
+            groups: context.groups,
+            handlers: context.handlers,
+            handler_names: context.handler_names,
+        })
+    }
+}
+
+impl Builder {
+    /// Read a UI description file and build the widgets
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Builder, BuildError> {
+        let path = path.as_ref();
+        let mut src = String::new();
+        if let Err(e) = File::open(path).and_then(|mut f| f.read_to_string(&mut src)) {
+            return Err(BuildError::ReadFileError(format!("{}: {}", path.display(), e)));
+        }
+        src.parse()
+    }
+
+    /// The top-level widget
+    pub fn root(&self) -> AnyWidget {
+        self.root.clone()
+    }
+
+    /// Look up a widget by its id
+    pub fn get(&self, id: &str) -> Option<AnyWidget> {
+        self.ids.get(id).cloned()
+    }
+
+    /// Look up a widget by its id. Returns None if the type does not match.
+    pub fn get_widget<T: WidgetType>(&self, id: &str) -> Option<Rc<T>> {
+        self.ids.get(id).and_then(|w| w.downcast::<T>())
+    }
+
+    /// Look up a group of RadioButtons by the name given to their `group` property
+    pub fn get_radio_group(&self, name: &str) -> Option<Rc<RadioGroup>> {
+        self.groups.get(name).cloned()
+    }
+
+    /// Connect a handler to the callbacks which name it.
+    /// Connecting the same name again replaces the handler.
+    pub fn connect<F>(&self, name: &str, f: F) where F: Fn(Signal) + 'static {
+        self.handlers.borrow_mut().insert(name.to_owned(), Rc::new(f));
+    }
+
+    /// Handler names used in the description which are not connected yet
+    pub fn unconnected_handlers(&self) -> Vec<String> {
+        let handlers = self.handlers.borrow();
+        self.handler_names.iter().filter(|n| !handlers.contains_key(*n)).cloned().collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum BuildError {
+    ReadFileError(String),
+    /// Line and column (from 1) of the error and the message
+    ParseError(usize, usize, String),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::ReadFileError(ref s) => {
+                write!(f, "Failed reading UI description \"{}\"", s)
+            },
+            BuildError::ParseError(line, column, ref s) => {
+                write!(f, "line {}, column {}: {}", line, column, s)
+            },
+        }
+    }
+}
+
+impl ::std::error::Error for BuildError {
+    fn description(&self) -> &str {
+        "UI description loading failed"
+    }
+}
+
+/// Line and column
+type Pos = (usize, usize);
+
+fn error<T>(pos: Pos, msg: String) -> Result<T, BuildError> {
+    Err(BuildError::ParseError(pos.0, pos.1, msg))
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Punct(char),
+    Eof,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match *self {
+            Token::Ident(ref s) => format!("\"{}\"", s),
+            Token::Str(_) => "string".to_owned(),
+            Token::Int(_) | Token::Float(_) => "number".to_owned(),
+            Token::Punct(c) => format!("'{}'", c),
+            Token::Eof => "end of file".to_owned(),
+        }
+    }
+}
+
+struct Lexer<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        match c {
+            Some('\n') => { self.line += 1; self.column = 1; },
+            Some(_) => { self.column += 1; },
+            None => (),
+        }
+        c
+    }
+
+    fn skip_spaces_and_comments(&mut self) {
+        loop {
+            match self.chars.peek().cloned() {
+                Some(c) if c.is_whitespace() => { self.next_char(); },
+                Some('/') => {
+                    let mut ahead = self.chars.clone();
+                    ahead.next();
+                    if ahead.next() != Some('/') {
+                        return;
+                    }
+                    while let Some(c) = self.next_char() {
+                        if c == '\n' { break; }
+                    }
+                },
+                _ => { return; },
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<(Token, Pos), BuildError> {
+        self.skip_spaces_and_comments();
+        let pos = (self.line, self.column);
+
+        let c = match self.chars.peek().cloned() {
+            Some(c) => c,
+            None => { return Ok((Token::Eof, pos)); },
+        };
+
+        if c.is_alphabetic() || c == '_' {
+            let mut s = String::new();
+            while let Some(c) = self.chars.peek().cloned() {
+                if !(c.is_alphanumeric() || c == '_') { break; }
+                s.push(c);
+                self.next_char();
+            }
+            return Ok((Token::Ident(s), pos));
+        }
+
+        if c.is_digit(10) || c == '-' {
+            let mut s = String::new();
+            s.push(c);
+            self.next_char();
+            while let Some(c) = self.chars.peek().cloned() {
+                if !(c.is_digit(10) || c == '.') { break; }
+                s.push(c);
+                self.next_char();
+            }
+            if let Ok(i) = s.parse::<i64>() {
+                return Ok((Token::Int(i), pos));
+            }
+            if let Ok(f) = s.parse::<f64>() {
+                return Ok((Token::Float(f), pos));
+            }
+            return error(pos, format!("invalid number \"{}\"", s));
+        }
+
+        if c == '"' {
+            self.next_char();
+            let mut s = String::new();
+            loop {
+                match self.next_char() {
+                    Some('"') => { break; },
+                    Some('\\') => {
+                        let escape_pos = (self.line, self.column - 1);
+                        match self.next_char() {
+                            Some('n') => s.push('\n'),
+                            Some('t') => s.push('\t'),
+                            Some('"') => s.push('"'),
+                            Some('\\') => s.push('\\'),
+                            _ => { return error(escape_pos, "invalid escape in string".to_owned()); },
+                        }
+                    },
+                    Some(c) => s.push(c),
+                    None => { return error(pos, "unterminated string".to_owned()); },
+                }
+            }
+            return Ok((Token::Str(s), pos));
+        }
+
+        match c {
+            '(' | ')' | '{' | '}' | '[' | ']' | ',' | ':' => {
+                self.next_char();
+                Ok((Token::Punct(c), pos))
+            },
+            _ => error(pos, format!("unexpected character '{}'", c)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Ident(String),
+    List(Vec<Value>),
+}
+
+struct Property {
+    name: String,
+    value: Value,
+    pos: Pos,
+    used: Cell<bool>,
+}
+
+/// A widget in the description
+struct Node {
+    type_name: String,
+    pos: Pos,
+    props: Vec<Property>,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn prop(&self, name: &str) -> Option<&Property> {
+        let p = self.props.iter().find(|p| p.name == name);
+        if let Some(p) = p {
+            p.used.set(true);
+        }
+        p
+    }
+
+    fn get_str(&self, name: &str) -> Result<Option<String>, BuildError> {
+        match self.prop(name) {
+            Some(&Property{ value: Value::Str(ref s), .. }) => Ok(Some(s.clone())),
+            Some(p) => error(p.pos, format!("\"{}\" must be a string", name)),
+            None => Ok(None),
+        }
+    }
+
+    fn get_bool(&self, name: &str) -> Result<Option<bool>, BuildError> {
+        match self.prop(name) {
+            Some(&Property{ value: Value::Bool(b), .. }) => Ok(Some(b)),
+            Some(p) => error(p.pos, format!("\"{}\" must be true or false", name)),
+            None => Ok(None),
+        }
+    }
+
+    fn get_float(&self, name: &str) -> Result<Option<f64>, BuildError> {
+        match self.prop(name) {
+            Some(&Property{ value: Value::Float(f), .. }) => Ok(Some(f)),
+            Some(&Property{ value: Value::Int(i), .. }) => Ok(Some(i as f64)),
+            Some(p) => error(p.pos, format!("\"{}\" must be a number", name)),
+            None => Ok(None),
+        }
+    }
+
+    fn get_u32(&self, name: &str) -> Result<Option<u32>, BuildError> {
+        match self.prop(name) {
+            Some(&Property{ value: Value::Int(i), .. }) if i >= 0 && i <= u32::max_value() as i64 => {
+                Ok(Some(i as u32))
+            },
+            Some(p) => error(p.pos, format!("\"{}\" must be a non-negative integer", name)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get a list of integers with the given length
+    fn get_int_list(&self, name: &str, len: usize) -> Result<Option<Vec<i32>>, BuildError> {
+        let p = match self.prop(name) {
+            Some(p) => p,
+            None => { return Ok(None); },
+        };
+        if let Value::List(ref values) = p.value {
+            let ints: Vec<i32> = values.iter().filter_map(|v| match *v {
+                Value::Int(i) => Some(i as i32),
+                _ => None,
+            }).collect();
+            if ints.len() == len && values.len() == len {
+                return Ok(Some(ints));
+            }
+        }
+        error(p.pos, format!("\"{}\" must be a list of {} integers", name, len))
+    }
+
+    /// Get an enum value given by its name
+    fn get_enum<T: Copy>(&self, name: &str, choices: &[(&str, T)]) -> Result<Option<T>, BuildError> {
+        let p = match self.prop(name) {
+            Some(p) => p,
+            None => { return Ok(None); },
+        };
+        if let Value::Ident(ref s) = p.value {
+            if let Some(c) = choices.iter().find(|c| c.0 == s) {
+                return Ok(Some(c.1));
+            }
+        }
+        let names: Vec<&str> = choices.iter().map(|c| c.0).collect();
+        error(p.pos, format!("\"{}\" must be one of {}", name, names.join(", ")))
+    }
+
+    fn get_align(&self, name: &str) -> Result<Option<Align>, BuildError> {
+        self.get_enum(name, &[
+            ("Fill", Align::Fill), ("Start", Align::Start),
+            ("Center", Align::Center), ("End", Align::End)])
+    }
+
+    fn require<T>(&self, name: &str, value: Option<T>) -> Result<T, BuildError> {
+        match value {
+            Some(v) => Ok(v),
+            None => error(self.pos, format!("{} needs property \"{}\"", self.type_name, name)),
+        }
+    }
+
+    /// Fails if some properties were not read by the widget or its container
+    fn check_unused(&self) -> Result<(), BuildError> {
+        if let Some(p) = self.props.iter().find(|p| !p.used.get()) {
+            return error(p.pos, format!("unknown property \"{}\" for {}", p.name, self.type_name));
+        }
+        Ok(())
+    }
+}
+
+struct Parser {
+    tokens: Vec<(Token, Pos)>,
+    index: usize,
+}
+
+impl Parser {
+    fn new(src: &str) -> Result<Parser, BuildError> {
+        let mut lexer = Lexer{ chars: src.chars().peekable(), line: 1, column: 1 };
+        let mut tokens = Vec::new();
+        loop {
+            let t = try!(lexer.next_token());
+            let eof = t.0 == Token::Eof;
+            tokens.push(t);
+            if eof { break; }
+        }
+        Ok(Parser{ tokens: tokens, index: 0 })
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.index].0
+    }
+
+    fn pos(&self) -> Pos {
+        self.tokens[self.index].1
+    }
+
+    fn next(&mut self) -> (Token, Pos) {
+        let t = self.tokens[self.index].clone();
+        if self.index + 1 < self.tokens.len() {
+            self.index += 1;
+        }
+        t
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), BuildError> {
+        let (t, pos) = self.next();
+        if t == Token::Punct(c) {
+            Ok(())
+        }else{
+            error(pos, format!("expected '{}', found {}", c, t.describe()))
+        }
+    }
+
+    fn parse_document(&mut self) -> Result<Node, BuildError> {
+        let node = try!(self.parse_node());
+        let (t, pos) = self.next();
+        if t != Token::Eof {
+            return error(pos, format!("expected end of file, found {}", t.describe()));
+        }
+        Ok(node)
+    }
+
+    fn parse_node(&mut self) -> Result<Node, BuildError> {
+        let (t, pos) = self.next();
+        let type_name = match t {
+            Token::Ident(s) => s,
+            t => { return error(pos, format!("expected widget type, found {}", t.describe())); },
+        };
+        let mut node = Node{ type_name: type_name, pos: pos, props: Vec::new(), children: Vec::new() };
+
+        if *self.peek() == Token::Punct('(') {
+            self.next();
+            while *self.peek() != Token::Punct(')') {
+                let (t, pos) = self.next();
+                let name = match t {
+                    Token::Ident(s) => s,
+                    t => { return error(pos, format!("expected property name, found {}", t.describe())); },
+                };
+                if node.props.iter().any(|p| p.name == name) {
+                    return error(pos, format!("property \"{}\" is given twice", name));
+                }
+                try!(self.expect(':'));
+                let value = try!(self.parse_value());
+                node.props.push(Property{ name: name, value: value, pos: pos, used: Cell::new(false) });
+
+                if *self.peek() == Token::Punct(',') {
+                    self.next();
+                }else{
+                    break;
+                }
+            }
+            try!(self.expect(')'));
+        }
+
+        if *self.peek() == Token::Punct('{') {
+            self.next();
+            while *self.peek() != Token::Punct('}') {
+                if *self.peek() == Token::Eof {
+                    return error(self.pos(), "expected '}', found end of file".to_owned());
+                }
+                node.children.push(try!(self.parse_node()));
+            }
+            self.next();
+        }
+
+        Ok(node)
+    }
+
+    fn parse_value(&mut self) -> Result<Value, BuildError> {
+        let (t, pos) = self.next();
+        match t {
+            Token::Str(s) => Ok(Value::Str(s)),
+            Token::Int(i) => Ok(Value::Int(i)),
+            Token::Float(f) => Ok(Value::Float(f)),
+            Token::Ident(ref s) if s == "true" => Ok(Value::Bool(true)),
+            Token::Ident(ref s) if s == "false" => Ok(Value::Bool(false)),
+            Token::Ident(s) => Ok(Value::Ident(s)),
+            Token::Punct('[') => {
+                let mut values = Vec::new();
+                while *self.peek() != Token::Punct(']') {
+                    values.push(try!(self.parse_value()));
+                    if *self.peek() == Token::Punct(',') {
+                        self.next();
+                    }else{
+                        break;
+                    }
+                }
+                try!(self.expect(']'));
+                Ok(Value::List(values))
+            },
+            t => error(pos, format!("expected value, found {}", t.describe())),
+        }
+    }
+}
+
+struct BuildContext {
+    ids: HashMap<String, AnyWidget>,
+    groups: HashMap<String, Rc<RadioGroup>>,
+    handlers: Handlers,
+    handler_names: Vec<String>,
+}
+
+impl BuildContext {
+    /// Build the widget and its children.
+    /// Unused properties of the children are checked here, after the container read them.
+    fn build(&mut self, node: &Node) -> Result<AnyWidget, BuildError> {
+        let widget = try!(self.build_widget(node));
+
+        for c in &node.children {
+            try!(c.check_unused());
+        }
+
+        if let Some(align) = try!(node.get_align("halign")) {
+            widget.set_halign(align);
+        }
+        if let Some(align) = try!(node.get_align("valign")) {
+            widget.set_valign(align);
+        }
+        if let Some(expand) = try!(node.get_bool("hexpand")) {
+            widget.set_hexpand(expand);
+        }
+        if let Some(expand) = try!(node.get_bool("vexpand")) {
+            widget.set_vexpand(expand);
+        }
+        let (min_width, min_height) = widget.get_min_size();
+        let (max_width, max_height) = widget.get_max_size();
+        widget.set_min_size(try!(node.get_u32("min_width")).or(min_width),
+                            try!(node.get_u32("min_height")).or(min_height));
+        widget.set_max_size(try!(node.get_u32("max_width")).or(max_width),
+                            try!(node.get_u32("max_height")).or(max_height));
+        if let Some(size) = try!(node.get_int_list("fixed_size", 2)) {
+            if size[0] < 0 || size[1] < 0 {
+                return error(node.prop("fixed_size").unwrap().pos, "negative size in \"fixed_size\"".to_owned());
+            }
+            widget.set_fixed_size(size[0] as u32, size[1] as u32);
+        }
+        if let Some(ratio) = try!(node.get_float("aspect_ratio")) {
+            widget.set_aspect_ratio(Some(ratio));
+        }
+        if let Some(visible) = try!(node.get_bool("visible")) {
+            widget.set_visible(visible);
+        }
+        if let Some(sensitive) = try!(node.get_bool("sensitive")) {
+            widget.set_sensitive(sensitive);
+        }
+        if let Some(id) = try!(node.get_str("id")) {
+            if self.ids.contains_key(&id) {
+                return error(node.prop("id").unwrap().pos, format!("id \"{}\" is used twice", id));
+            }
+            self.ids.insert(id, widget.clone());
+        }
+
+        Ok(widget)
+    }
+
+    /// Create a callback which calls the handler named by the property
+    fn handler(&mut self, node: &Node, name: &str) -> Result<Option<Box<Fn(Signal)>>, BuildError> {
+        let handler_name = match try!(node.get_str(name)) {
+            Some(s) => s,
+            None => { return Ok(None); },
+        };
+        if !self.handler_names.contains(&handler_name) {
+            self.handler_names.push(handler_name.clone());
+        }
+
+        let handlers = self.handlers.clone();
+        Ok(Some(Box::new(move |signal| {
+            // Clone the handler because it may connect other handlers
+            let f = handlers.borrow().get(&handler_name).cloned();
+            if let Some(f) = f {
+                f(signal);
+            }
+        })))
+    }
+
+    fn no_children(&self, node: &Node) -> Result<(), BuildError> {
+        if let Some(c) = node.children.first() {
+            return error(c.pos, format!("{} cannot have children", node.type_name));
+        }
+        Ok(())
+    }
+
+    fn single_child(&mut self, node: &Node) -> Result<Option<AnyWidget>, BuildError> {
+        if let Some(c) = node.children.get(1) {
+            return error(c.pos, format!("{} can have only one child", node.type_name));
+        }
+        match node.children.first() {
+            Some(c) => self.build(c).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn build_widget(&mut self, node: &Node) -> Result<AnyWidget, BuildError> {
+        let widget: AnyWidget = match &*node.type_name {
+            "VBox" | "HBox" => {
+                macro_rules! setup_box {
+                    ($b:expr) => {{
+                        let b = $b;
+                        if let Some(s) = try!(node.get_u32("spacing")) {
+                            b.set_spacing(s);
+                        }
+                        if let Some(h) = try!(node.get_bool("homogeneous")) {
+                            b.set_homogeneous(h);
+                        }
+                        for c in &node.children {
+                            let w = try!(self.build(c));
+                            let expand = try!(c.get_bool("pack_expand")).unwrap_or(false);
+                            let fill = try!(c.get_bool("pack_fill")).unwrap_or(true);
+                            let padding = try!(c.get_u32("pack_padding")).unwrap_or(0);
+                            if try!(c.get_bool("pack_end")).unwrap_or(false) {
+                                b.pack_end(w, expand, fill, padding);
+                            }else{
+                                b.pack_start(w, expand, fill, padding);
+                            }
+                        }
+                        AnyWidget::from(b)
+                    }}
+                }
+                if node.type_name == "VBox" {
+                    setup_box!(VBox::new())
+                }else{
+                    setup_box!(HBox::new())
+                }
+            },
+            "FlowBox" => {
+                let flowbox = FlowBox::new();
+                if let Some(s) = try!(node.get_u32("row_spacing")) {
+                    flowbox.set_row_spacing(s);
+                }
+                if let Some(s) = try!(node.get_u32("column_spacing")) {
+                    flowbox.set_column_spacing(s);
+                }
+                if let Some(align) = try!(node.get_align("line_align")) {
+                    flowbox.set_line_align(align);
+                }
+                for c in &node.children {
+                    flowbox.add(try!(self.build(c)));
+                }
+                flowbox.into()
+            },
+            "Layout" => {
+                let layout = Layout::new();
+                for c in &node.children {
+                    let w = try!(self.build(c));
+                    let r = try!(c.require("rect", try!(c.get_int_list("rect", 4))));
+                    if r[2] < 0 || r[3] < 0 {
+                        return error(c.prop("rect").unwrap().pos, "negative size in \"rect\"".to_owned());
+                    }
+                    layout.add_anchored(w, Anchor::fixed(Rect::new(r[0], r[1], r[2] as u32, r[3] as u32)));
+                }
+                layout.into()
+            },
+            "Notebook" => {
+                let notebook = Notebook::new();
+                for c in &node.children {
+                    let w = try!(self.build(c));
+                    let tab = try!(c.require("tab", try!(c.get_str("tab"))));
+                    notebook.append_page_with_label(w, &tab);
+                }
+                if let Some(page) = try!(node.get_u32("current_page")) {
+                    notebook.set_current_page(page as usize);
+                }
+                if let Some(f) = try!(self.handler(node, "on_page_changed")) {
+                    notebook.on_page_changed(move |page| f(Signal::PageChanged(page)));
+                }
+                notebook.into()
+            },
+            "Stack" => {
+                let stack = Stack::new();
+                for c in &node.children {
+                    let w = try!(self.build(c));
+                    let name = try!(c.require("name", try!(c.get_str("name"))));
+                    stack.add_named(w, &name);
+                }
+                if let Some(t) = try!(node.get_enum("transition", &[
+                    ("None", StackTransition::None), ("Crossfade", StackTransition::Crossfade),
+                    ("SlideLeft", StackTransition::SlideLeft), ("SlideRight", StackTransition::SlideRight),
+                    ("SlideUp", StackTransition::SlideUp), ("SlideDown", StackTransition::SlideDown)])) {
+                    stack.set_transition_type(t);
+                }
+                if let Some(ms) = try!(node.get_u32("transition_duration")) {
+                    stack.set_transition_duration(Duration::from_millis(ms as u64));
+                }
+                if let Some(name) = try!(node.get_str("visible_child")) {
+                    if !stack.set_visible_child(&name) {
+                        return error(node.prop("visible_child").unwrap().pos,
+                                     format!("no child named \"{}\"", name));
+                    }
+                }
+                stack.into()
+            },
+            "HPaned" | "VPaned" => {
+                if let Some(c) = node.children.get(2) {
+                    return error(c.pos, format!("{} can have only two children", node.type_name));
+                }
+                let mut children = Vec::new();
+                for c in &node.children {
+                    let w = try!(self.build(c));
+                    children.push((w, try!(c.get_bool("shrink")).unwrap_or(true)));
+                }
+                let position = try!(node.get_u32("position"));
+                let callback = try!(self.handler(node, "on_position_changed"));
+
+                macro_rules! setup_paned {
+                    ($paned:expr) => {{
+                        let paned = $paned;
+                        let mut children = children.into_iter();
+                        if let Some((w, shrink)) = children.next() { paned.pack1(w, shrink); }
+                        if let Some((w, shrink)) = children.next() { paned.pack2(w, shrink); }
+                        if let Some(p) = position { paned.set_position(p); }
+                        if let Some(f) = callback {
+                            paned.on_position_changed(move |p| f(Signal::PositionChanged(p)));
+                        }
+                        AnyWidget::from(paned)
+                    }}
+                }
+                if node.type_name == "HPaned" {
+                    setup_paned!(HPaned::new())
+                }else{
+                    setup_paned!(VPaned::new())
+                }
+            },
+            "Overlay" => {
+                let overlay = Overlay::new();
+                for (i, c) in node.children.iter().enumerate() {
+                    let w = try!(self.build(c));
+                    if i == 0 {
+                        overlay.set_child(w);
+                    }else{
+                        let offset = try!(c.get_int_list("offset", 2)).unwrap_or(vec![0, 0]);
+                        overlay.add_overlay_with_offset(w, offset[0], offset[1]);
+                    }
+                }
+                overlay.into()
+            },
+            "MarginBox" => {
+                let top = try!(node.get_u32("top")).unwrap_or(0);
+                let bottom = try!(node.get_u32("bottom")).unwrap_or(0);
+                let left = try!(node.get_u32("left")).unwrap_or(0);
+                let right = try!(node.get_u32("right")).unwrap_or(0);
+                let marginbox = MarginBox::empty(top, bottom, left, right);
+                if let Some(w) = try!(self.single_child(node)) {
+                    marginbox.set_child(w);
+                }
+                marginbox.into()
+            },
+            "CenteringBox" => {
+                let centeringbox = CenteringBox::empty();
+                if let Some(w) = try!(self.single_child(node)) {
+                    centeringbox.set_child(w);
+                }
+                centeringbox.into()
+            },
+            "ScrolledWindow" => {
+                let window = ScrolledWindow::new();
+                let policies = [
+                    ("Always", ScrollPolicy::Always), ("Automatic", ScrollPolicy::Automatic),
+                    ("Never", ScrollPolicy::Never)];
+                let (hpolicy, vpolicy) = window.get_policy();
+                let hpolicy = try!(node.get_enum("hpolicy", &policies)).unwrap_or(hpolicy);
+                let vpolicy = try!(node.get_enum("vpolicy", &policies)).unwrap_or(vpolicy);
+                window.set_policy(hpolicy, vpolicy);
+                if let Some(w) = try!(self.single_child(node)) {
+                    window.set_child(w);
+                }
+                window.into()
+            },
+            "Expander" => {
+                let expander = Expander::new(&try!(node.get_str("label")).unwrap_or_default());
+                if let Some(w) = try!(self.single_child(node)) {
+                    expander.set_child(w);
+                }
+                if let Some(expanded) = try!(node.get_bool("expanded")) {
+                    expander.set_expanded(expanded);
+                }
+                if let Some(f) = try!(self.handler(node, "on_expanded_changed")) {
+                    expander.on_expanded_changed(move |e| f(Signal::ExpandedChanged(e)));
+                }
+                expander.into()
+            },
+            "Button" => {
+                try!(self.no_children(node));
+                let button = match try!(node.get_str("label")) {
+                    Some(s) => Button::new_with_label(&s),
+                    None => Button::new(),
+                };
+                if let Some(f) = try!(self.handler(node, "on_clicked")) {
+                    button.on_clicked(move || f(Signal::Clicked));
+                }
+                button.into()
+            },
+            "CheckButton" => {
+                try!(self.no_children(node));
+                let button = match try!(node.get_str("label")) {
+                    Some(s) => CheckButton::new_with_label(&s),
+                    None => CheckButton::new(),
+                };
+                if let Some(state) = try!(node.get_enum("state", &[
+                    ("Inactive", CheckState::Inactive), ("Active", CheckState::Active),
+                    ("Inconsistent", CheckState::Inconsistent)])) {
+                    button.set_state(state);
+                }
+                if let Some(f) = try!(self.handler(node, "on_toggled")) {
+                    button.on_toggled(move |active| f(Signal::Toggled(active)));
+                }
+                button.into()
+            },
+            "RadioButton" => {
+                try!(self.no_children(node));
+                // Buttons with the same group name share a group
+                let group_name = try!(node.get_str("group")).unwrap_or_default();
+                let group = self.groups.entry(group_name).or_insert_with(RadioGroup::new).clone();
+                let button = match try!(node.get_str("label")) {
+                    Some(s) => RadioButton::new_with_label(&group, &s),
+                    None => RadioButton::new(&group),
+                };
+                if let Some(value) = try!(node.get_str("value")) {
+                    button.set_value(&value);
+                }
+                if let Some(true) = try!(node.get_bool("active")) {
+                    button.set_active();
+                }
+                if let Some(f) = try!(self.handler(node, "on_selection_changed")) {
+                    group.on_selection_changed(move |index, _| f(Signal::SelectionChanged(index)));
+                }
+                button.into()
+            },
+            "Label" => {
+                try!(self.no_children(node));
+                let label = Label::new(&try!(node.get_str("text")).unwrap_or_default());
+                if let Some(wrap) = try!(node.get_bool("wrap")) {
+                    label.set_wrap(wrap);
+                }
+                label.into()
+            },
+            "Entry" => {
+                try!(self.no_children(node));
+                let entry = Entry::new();
+                if let Some(v) = try!(node.get_u32("max_length")) {
+                    entry.set_max_length(Some(v as usize));
+                }
+                if let Some(text) = try!(node.get_str("text")) {
+                    entry.set_text(&text);
+                }
+                if let Some(placeholder) = try!(node.get_str("placeholder")) {
+                    entry.set_placeholder(&placeholder);
+                }
+                if let Some(f) = try!(self.handler(node, "on_changed")) {
+                    entry.on_changed(move |_| f(Signal::Changed));
+                }
+                if let Some(f) = try!(self.handler(node, "on_activate")) {
+                    entry.on_activate(move || f(Signal::Activated));
+                }
+                entry.into()
+            },
+            "TextView" => {
+                try!(self.no_children(node));
+                let view = TextView::new();
+                if let Some(text) = try!(node.get_str("text")) {
+                    view.set_text(&text);
+                }
+                if let Some(wrap) = try!(node.get_bool("wrap")) {
+                    view.set_wrap(wrap);
+                }
+                if let Some(editable) = try!(node.get_bool("editable")) {
+                    view.set_editable(editable);
+                }
+                if let Some(f) = try!(self.handler(node, "on_changed")) {
+                    view.get_buffer().on_changed(move || f(Signal::Changed));
+                }
+                view.into()
+            },
+            "HScrollBar" | "VScrollBar" => {
+                try!(self.no_children(node));
+                macro_rules! setup_scrollbar {
+                    ($scrollbar:expr) => {{
+                        let scrollbar = $scrollbar;
+                        if let Some(v) = try!(node.get_float("lower")) {
+                            scrollbar.set_lower(v);
+                        }
+                        if let Some(v) = try!(node.get_float("upper")) {
+                            scrollbar.set_upper(v);
+                        }
+                        if let Some(v) = try!(node.get_float("page_size")) {
+                            scrollbar.set_page_size(v);
+                        }
+                        if let Some(v) = try!(node.get_float("step")) {
+                            scrollbar.set_step(v);
+                        }
+                        if let Some(v) = try!(node.get_float("value")) {
+                            scrollbar.set_value(v);
+                        }
+                        if let Some(f) = try!(self.handler(node, "on_value_changed")) {
+                            scrollbar.on_value_changed(move |v| f(Signal::ValueChanged(v)));
+                        }
+                        AnyWidget::from(scrollbar)
+                    }}
+                }
+                if node.type_name == "HScrollBar" {
+                    setup_scrollbar!(HScrollBar::new())
+                }else{
+                    setup_scrollbar!(VScrollBar::new())
+                }
+            },
+            "HScale" | "VScale" => {
+                try!(self.no_children(node));
+                macro_rules! setup_scale {
+                    ($scale:expr) => {{
+                        let scale = $scale;
+                        if let Some(v) = try!(node.get_float("lower")) {
+                            scale.set_lower(v);
+                        }
+                        if let Some(v) = try!(node.get_float("upper")) {
+                            scale.set_upper(v);
+                        }
+                        if let Some(v) = try!(node.get_float("step")) {
+                            scale.set_step(v);
+                        }
+                        if let Some(v) = try!(node.get_float("value")) {
+                            scale.set_value(v);
+                        }
+                        if let Some(v) = try!(node.get_float("tick_interval")) {
+                            scale.set_tick_interval(Some(v));
+                        }
+                        if let Some(v) = try!(node.get_bool("draw_value")) {
+                            scale.set_draw_value(v);
+                        }
+                        if let Some(f) = try!(self.handler(node, "on_value_changed")) {
+                            scale.on_value_changed(move |v| f(Signal::ValueChanged(v)));
+                        }
+                        AnyWidget::from(scale)
+                    }}
+                }
+                if node.type_name == "HScale" {
+                    setup_scale!(HScale::new())
+                }else{
+                    setup_scale!(VScale::new())
+                }
+            },
+            "DrawingArea" => {
+                try!(self.no_children(node));
+                DrawingArea::new().into()
+            },
+            "Empty" => {
+                try!(self.no_children(node));
+                Empty::new().into()
+            },
+            _ => {
+                return error(node.pos, format!("unknown widget type \"{}\"", node.type_name));
+            },
+        };
+        Ok(widget)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BuildError, Lexer, Parser, Node, Token, Value};
+
+    fn tokens(src: &str) -> Vec<Token> {
+        let mut lexer = Lexer{ chars: src.chars().peekable(), line: 1, column: 1 };
+        let mut result = Vec::new();
+        loop {
+            let (t, _) = lexer.next_token().unwrap();
+            if t == Token::Eof { break; }
+            result.push(t);
+        }
+        result
+    }
+
+    fn parse(src: &str) -> Result<Node, BuildError> {
+        Parser::new(src).and_then(|mut p| p.parse_document())
+    }
+
+    fn error_pos(src: &str) -> (usize, usize) {
+        match parse(src) {
+            Err(BuildError::ParseError(line, column, _)) => (line, column),
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("parsed invalid source {:?}", src),
+        }
+    }
+
+    #[test]
+    fn lexer_tokens() {
+        assert_eq!(tokens("Label(text: \"a\\\"b\\n\") // comment\n{ }"), vec![
+            Token::Ident("Label".to_owned()), Token::Punct('('), Token::Ident("text".to_owned()),
+            Token::Punct(':'), Token::Str("a\"b\n".to_owned()), Token::Punct(')'),
+            Token::Punct('{'), Token::Punct('}'),
+        ]);
+        assert_eq!(tokens("12 -3 0.5 -1.25"), vec![
+            Token::Int(12), Token::Int(-3), Token::Float(0.5), Token::Float(-1.25),
+        ]);
+    }
+
+    #[test]
+    fn property_types() {
+        let node = parse("Scale(s: \"x\", i: 3, f: 1.5, b: true, c: false, e: Center, l: [1, 2, 3])").unwrap();
+        let values: Vec<&Value> = node.props.iter().map(|p| &p.value).collect();
+        match values[0] { &Value::Str(ref s) => assert_eq!(s, "x"), v => panic!("{:?}", v) }
+        match values[1] { &Value::Int(3) => (), v => panic!("{:?}", v) }
+        match values[2] { &Value::Float(f) => assert_eq!(f, 1.5), v => panic!("{:?}", v) }
+        match values[3] { &Value::Bool(true) => (), v => panic!("{:?}", v) }
+        match values[4] { &Value::Bool(false) => (), v => panic!("{:?}", v) }
+        match values[5] { &Value::Ident(ref s) => assert_eq!(s, "Center"), v => panic!("{:?}", v) }
+        match values[6] { &Value::List(ref l) => assert_eq!(l.len(), 3), v => panic!("{:?}", v) }
+
+        assert_eq!(node.get_u32("i").unwrap(), Some(3));
+        assert_eq!(node.get_float("i").unwrap(), Some(3.0));
+        assert_eq!(node.get_int_list("l", 3).unwrap(), Some(vec![1, 2, 3]));
+        assert!(node.get_str("i").is_err());
+        assert!(node.get_int_list("l", 2).is_err());
+    }
+
+    #[test]
+    fn nesting() {
+        let node = parse("VBox {\n  HBox(spacing: 2) {\n    Label\n    Button(label: \"OK\")\n  }\n  Empty\n}").unwrap();
+        assert_eq!(node.type_name, "VBox");
+        assert_eq!(node.children.len(), 2);
+        let hbox = &node.children[0];
+        assert_eq!(hbox.type_name, "HBox");
+        assert_eq!(hbox.pos, (2, 3));
+        assert_eq!(hbox.children.iter().map(|c| &c.type_name[..]).collect::<Vec<_>>(), vec!["Label", "Button"]);
+        assert_eq!(node.children[1].type_name, "Empty");
+        assert!(node.children[1].children.is_empty());
+    }
+
+    #[test]
+    fn unused_property() {
+        let node = parse("Label(text: \"a\",\n      colour: 1)").unwrap();
+        node.get_str("text").unwrap();
+        match node.check_unused() {
+            Err(BuildError::ParseError(2, 7, _)) => (),
+            r => panic!("{:?}", r.err()),
+        }
+    }
+
+    #[test]
+    fn error_positions() {
+        assert_eq!(error_pos("Label(text \"a\")"), (1, 12));
+        assert_eq!(error_pos("VBox {\n  Label\n"), (3, 1));
+        assert_eq!(error_pos("Label(text: \"a)"), (1, 13));
+        assert_eq!(error_pos("Label(text: \"\\q\")"), (1, 14));
+        assert_eq!(error_pos("Label(a: 1, a: 2)"), (1, 13));
+        assert_eq!(error_pos("Label\nLabel"), (2, 1));
+        assert_eq!(error_pos("Label(a: 1.2.3)"), (1, 10));
+        assert_eq!(error_pos("Label(a: @)"), (1, 10));
+    }
+}
@@ -10,3 +10,52 @@ pub enum Direction {
     Horizon, Vertical,
 }
 
+
+/// Which end of a box a child is packed from
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PackType {
+    Start, End,
+}
+
+/// When scrollbars are shown
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ScrollPolicy {
+    Always,
+    /// Shown only if the child is larger than the viewport
+    Automatic,
+    /// The child is shrunk to the viewport and cannot be scrolled
+    Never,
+}
+
+/// Animation used when a Stack switches the visible child
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum StackTransition {
+    None,
+    /// The new child fades in over the old child
+    Crossfade,
+    /// The new child comes from the right
+    SlideLeft,
+    /// The new child comes from the left
+    SlideRight,
+    /// The new child comes from the bottom
+    SlideUp,
+    /// The new child comes from the top
+    SlideDown,
+}
+
+/// How a widget is placed in the space allocated by its container
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Align {
+    /// Use all of the space
+    Fill,
+    Start, Center, End,
+}
+
+/// State of a CheckButton
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CheckState {
+    Inactive,
+    Active,
+    /// Neither active nor inactive, e.g. for a group of partly enabled options
+    Inconsistent,
+}
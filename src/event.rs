@@ -1,12 +1,48 @@
 
 use prelude::*;
 use ::sdl2::mouse::Mouse;
+use ::sdl2::keyboard::{Keycode, Mod};
 
 /// Events are processed by widgets which window owns.
 #[derive(Clone)]
 pub enum Event<'a> {
     ButtonDown((i32, i32), Mouse),
     ButtonUp((i32, i32), Mouse),
+    MouseMotion((i32, i32)),
+    /// Pointer position and scroll amount
+    MouseWheel((i32, i32), (i32, i32)),
+    KeyDown(Keycode, Mod),
+    /// Text typed by the user, already composed by the input method
+    TextInput(String),
     RendererChanged(&'a Renderer<'a>),
     WindowFocusLost,
 }
+
+impl<'a> Event<'a> {
+    /// Events caused by the mouse or the keyboard, which insensitive widgets ignore
+    pub fn is_input(&self) -> bool {
+        match *self {
+            Event::ButtonDown(..) | Event::ButtonUp(..) | Event::MouseMotion(..) |
+            Event::MouseWheel(..) | Event::KeyDown(..) | Event::TextInput(..) => true,
+            Event::RendererChanged(..) | Event::WindowFocusLost => false,
+        }
+    }
+
+    /// Events sent only to the widget which has the keyboard focus and its ancestors
+    pub fn is_key(&self) -> bool {
+        match *self {
+            Event::KeyDown(..) | Event::TextInput(..) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Returns true if Ctrl key is held
+pub fn ctrl_pressed(keymod: Mod) -> bool {
+    keymod.intersects(::sdl2::keyboard::LCTRLMOD | ::sdl2::keyboard::RCTRLMOD)
+}
+
+/// Returns true if Shift key is held
+pub fn shift_pressed(keymod: Mod) -> bool {
+    keymod.intersects(::sdl2::keyboard::LSHIFTMOD | ::sdl2::keyboard::RSHIFTMOD)
+}
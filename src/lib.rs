@@ -1,16 +1,23 @@
 
 extern crate sdl2;
 extern crate sdl2_ttf;
+pub extern crate cassowary;
+extern crate unicode_segmentation;
 
 #[macro_use]
 extern crate lazy_static;
 
+#[macro_use]
+mod macros;
+
 pub mod prelude;
 pub mod uicontext;
 pub mod traits;
 pub mod widgets;
 pub mod theme;
 pub mod enums;
+pub mod size;
+pub mod builder;
 mod event;
 
 
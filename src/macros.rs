@@ -0,0 +1,180 @@
+
+/// Build a widget tree declaratively.
+///
+/// Each widget is written as `Type::constructor(args)` with an optional body in braces,
+/// and can be given a name as `name = Type::constructor(args)`.
+/// The macro returns a tuple of the root widget and a struct which has a field
+/// for each named widget, holding an `Rc` of its type.
+/// Callbacks in the tree can use the widgets named before them.
+///
+/// In a body,
+/// * `.method(args);` calls a method of the widget to set properties or callbacks.
+/// * `@method(args) Type::constructor(args) ...` adds a child
+///   by calling `parent.method(child, args)`, e.g. `@pack(true)` for VBox or `@set_child()` for MarginBox.
+///
+/// Large trees may need a higher `#![recursion_limit]` in the crate using the macro.
+///
+/// ```no_run
+/// #[macro_use]
+/// extern crate tenuki_ui;
+///
+/// use tenuki_ui::prelude::*;
+/// use tenuki_ui::enums::Align;
+/// use tenuki_ui::widgets::Frame;
+///
+/// fn main() {
+///     let frame = Frame::new("ui! example", 300, 300, true);
+///     let (vbox, w) = ui! {
+///         VBox::new() {
+///             .set_spacing(4);
+///             @pack(true) label = Label::new("Click Button!") {
+///                 .set_valign(Align::Center);
+///             }
+///             @pack_end(false, true, 10) MarginBox::empty(0, 0, 10, 10) {
+///                 @set_child() button = Button::new_with_label("Button") {
+///                     .on_clicked({
+///                         let label = label.clone();
+///                         move || { label.set_text("Clicked!"); }
+///                     });
+///                 }
+///             }
+///         }
+///     };
+///     w.button.set_sensitive(true);
+///     w.label.set_text("Click Button!");
+///     frame.set_child(vbox);
+/// }
+/// ```
+#[macro_export]
+macro_rules! ui {
+    // Collect the names and types of named widgets, then build the tree
+    (@collect [$($name:ident : $ty:ident)*] [$($tree:tt)*]) => {{
+        #[allow(dead_code)]
+        struct Widgets {
+            $( $name: ::std::rc::Rc<$crate::widgets::$ty>, )*
+        }
+        ui!(@root root; $($tree)*);
+        (root, Widgets{ $( $name: $name, )* })
+    }};
+
+    (@collect [$($acc:tt)*] [$($tree:tt)*] $name:ident = $ty:ident :: $($rest:tt)*) => {
+        ui!(@collect [$($acc)* $name : $ty] [$($tree)*] $($rest)*)
+    };
+
+    (@collect [$($acc:tt)*] [$($tree:tt)*] { $($body:tt)* } $($rest:tt)*) => {
+        ui!(@collect [$($acc)*] [$($tree)*] $($body)* $($rest)*)
+    };
+
+    (@collect [$($acc:tt)*] [$($tree:tt)*] $skip:tt $($rest:tt)*) => {
+        ui!(@collect [$($acc)*] [$($tree)*] $($rest)*)
+    };
+
+    // End of a body
+    (@body $w:ident; ) => {};
+
+    // Property or callback
+    (@body $w:ident; . $method:ident ( $($arg:tt)* ) ; $($rest:tt)*) => {
+        $w.$method($($arg)*);
+        ui!(@body $w; $($rest)*);
+    };
+
+    // Named child with a body
+    (@body $w:ident; @ $pack:ident ( $($pack_arg:tt)* )
+     $name:ident = $ty:ident :: $ctor:ident ( $($arg:tt)* ) { $($body:tt)* } $($rest:tt)*) => {
+        let $name = $crate::widgets::$ty::$ctor($($arg)*);
+        ui!(@body $name; $($body)*);
+        $w.$pack($name.clone(), $($pack_arg)*);
+        ui!(@body $w; $($rest)*);
+    };
+
+    // Named child without a body
+    (@body $w:ident; @ $pack:ident ( $($pack_arg:tt)* )
+     $name:ident = $ty:ident :: $ctor:ident ( $($arg:tt)* ) ; $($rest:tt)*) => {
+        let $name = $crate::widgets::$ty::$ctor($($arg)*);
+        $w.$pack($name.clone(), $($pack_arg)*);
+        ui!(@body $w; $($rest)*);
+    };
+
+    // Anonymous child with a body
+    (@body $w:ident; @ $pack:ident ( $($pack_arg:tt)* )
+     $ty:ident :: $ctor:ident ( $($arg:tt)* ) { $($body:tt)* } $($rest:tt)*) => {
+        let child = $crate::widgets::$ty::$ctor($($arg)*);
+        ui!(@body child; $($body)*);
+        $w.$pack(child, $($pack_arg)*);
+        ui!(@body $w; $($rest)*);
+    };
+
+    // Anonymous child without a body
+    (@body $w:ident; @ $pack:ident ( $($pack_arg:tt)* )
+     $ty:ident :: $ctor:ident ( $($arg:tt)* ) ; $($rest:tt)*) => {
+        $w.$pack($crate::widgets::$ty::$ctor($($arg)*), $($pack_arg)*);
+        ui!(@body $w; $($rest)*);
+    };
+
+    // Root widget, named or not
+    (@root $root:ident; $name:ident = $ty:ident :: $ctor:ident ( $($arg:tt)* ) $($body:tt)*) => {
+        let $name = $crate::widgets::$ty::$ctor($($arg)*);
+        let $root = $name.clone();
+        ui!(@root_body $root; $($body)*);
+    };
+
+    (@root $root:ident; $ty:ident :: $ctor:ident ( $($arg:tt)* ) $($body:tt)*) => {
+        let $root = $crate::widgets::$ty::$ctor($($arg)*);
+        ui!(@root_body $root; $($body)*);
+    };
+
+    (@root_body $root:ident; { $($body:tt)* }) => {
+        ui!(@body $root; $($body)*);
+    };
+
+    (@root_body $root:ident; ) => {};
+
+    ($($tree:tt)*) => {
+        ui!(@collect [] [$($tree)*] $($tree)*)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::cell::Cell;
+
+    use prelude::*;
+
+    #[test]
+    fn nested_tree() {
+        let moved = Rc::new(Cell::new(None));
+        let (root, w) = ui! {
+            VBox::new() {
+                .set_spacing(4);
+                @pack(true) paned = HPaned::new() {
+                    .on_position_changed({
+                        let moved = moved.clone();
+                        move |p| moved.set(Some(p))
+                    });
+                    @pack1(false) left = Empty::new();
+                    @pack2(true) MarginBox::empty(1, 2, 3, 4) {
+                        @set_child() inner = HBox::new() {
+                            @pack(false) Empty::new();
+                        }
+                    }
+                }
+                @pack(false) Empty::new();
+            }
+        };
+
+        assert_eq!(root.children().len(), 2);
+        assert!(root.children()[0] == AnyWidget::from(w.paned.clone()));
+        assert!(w.paned.children()[0] == AnyWidget::from(w.left.clone()));
+        assert_eq!(w.inner.children().len(), 1);
+
+        w.paned.set_position(20);
+        assert_eq!(moved.get(), Some(w.paned.get_position()));
+    }
+
+    #[test]
+    fn named_root() {
+        let (root, w) = ui!(root = Empty::new());
+        assert!(Rc::ptr_eq(&root, &w.root));
+    }
+}
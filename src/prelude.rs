@@ -7,5 +7,8 @@ pub use traits::*;
 
 pub use event::Event;
 
+pub use size::SizeRange;
+
 pub use widgets::AnyWidget;
+pub use widgets::WidgetCommon;
 
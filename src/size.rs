@@ -0,0 +1,63 @@
+
+use std::cmp::max;
+
+/// Minimum and natural size of a widget in one dimension.
+/// A widget can be drawn at the minimum size,
+/// and the natural size is the size the widget wants to be drawn at.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct SizeRange {
+    pub minimum: u32,
+    pub natural: u32,
+}
+
+impl SizeRange {
+    pub fn new(minimum: u32, natural: u32) -> SizeRange {
+        SizeRange{
+            minimum: minimum,
+            natural: max(minimum, natural),
+        }
+    }
+
+    /// Minimum and natural sizes are the same
+    pub fn fixed(size: u32) -> SizeRange {
+        SizeRange{
+            minimum: size,
+            natural: size,
+        }
+    }
+
+    /// Add a fixed size to both of the sizes
+    pub fn add(self, size: u32) -> SizeRange {
+        SizeRange{
+            minimum: self.minimum + size,
+            natural: self.natural + size,
+        }
+    }
+
+    /// The sum of sizes. Used for widgets arranged in a row.
+    pub fn sum(self, other: SizeRange) -> SizeRange {
+        SizeRange{
+            minimum: self.minimum + other.minimum,
+            natural: self.natural + other.natural,
+        }
+    }
+
+    /// The larger of sizes. Used for widgets stacked over the same space.
+    pub fn max(self, other: SizeRange) -> SizeRange {
+        SizeRange{
+            minimum: max(self.minimum, other.minimum),
+            natural: max(self.natural, other.natural),
+        }
+    }
+
+    /// Choose a size for the given available space
+    pub fn fit(self, available: u32) -> u32 {
+        if available < self.minimum {
+            self.minimum
+        }else if available < self.natural {
+            available
+        }else{
+            self.natural
+        }
+    }
+}
@@ -16,12 +16,17 @@ struct FontTheme {
 
 #[derive(Clone, Copy, Debug)]
 pub struct ThemeColor {
-    pub background      : Color,
-    pub light_background: Color,
-    pub dark_background : Color,
-    pub light_border    : Color,
-    pub dark_border     : Color,
-    pub text            : Color,
+    pub background         : Color,
+    pub light_background   : Color,
+    pub dark_background    : Color,
+    pub light_border       : Color,
+    pub dark_border        : Color,
+    pub text               : Color,
+    pub insensitive_text   : Color,
+    /// Background of selected text
+    pub selection          : Color,
+    /// Drawn over insensitive widgets
+    pub insensitive_overlay: Color,
 }
 
 impl ThemeColor {
@@ -33,12 +38,15 @@ impl ThemeColor {
 impl Default for ThemeColor {
     fn default() -> ThemeColor {
         ThemeColor{
-            background      : Color::RGB(0xD4, 0xD4, 0xD4),
-            light_background: Color::RGB(0xFF, 0xFF, 0xFF),
-            dark_background : Color::RGB(0xC0, 0xC0, 0xC0),
-            light_border    : Color::RGB(0xF0, 0xF0, 0xF0),
-            dark_border     : Color::RGB(0x70, 0x70, 0x70),
-            text            : Color::RGB(0x00, 0x00, 0x00),
+            background         : Color::RGB(0xD4, 0xD4, 0xD4),
+            light_background   : Color::RGB(0xFF, 0xFF, 0xFF),
+            dark_background    : Color::RGB(0xC0, 0xC0, 0xC0),
+            light_border       : Color::RGB(0xF0, 0xF0, 0xF0),
+            dark_border        : Color::RGB(0x70, 0x70, 0x70),
+            text               : Color::RGB(0x00, 0x00, 0x00),
+            insensitive_text   : Color::RGB(0x80, 0x80, 0x80),
+            selection          : Color::RGB(0xA0, 0xC4, 0xF0),
+            insensitive_overlay: Color::RGBA(0xD4, 0xD4, 0xD4, 0x80),
         }
     }
 }
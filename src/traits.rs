@@ -1,5 +1,7 @@
 
 use ::prelude::*;
+use ::enums::Align;
+use ::uicontext::{request_redraw, request_relayout, request_focus};
 
 /// All widgets implement this trait
 pub trait WidgetTrait {
@@ -14,15 +16,169 @@ pub trait WidgetTrait {
     fn event_handler(&self, _event: &Event) {
     }
 
-    /// Return adequate size for this widget
-    fn requested_size(&self) -> Option<(u32, u32)> {
-        None
+    /// Cancel presses and drags in progress.
+    /// Called when the widget is hidden or made insensitive. Containers pass it to their children.
+    fn cancel_input(&self) {
+    }
+
+    /// Return properties common to all widgets
+    fn common(&self) -> &WidgetCommon;
+
+    /// Set horizontal alignment in the space allocated by the container
+    fn set_halign(&self, align: Align) {
+        self.common().halign.set(align);
+        request_relayout();
+    }
+
+    fn get_halign(&self) -> Align {
+        self.common().halign.get()
+    }
+
+    /// Set vertical alignment in the space allocated by the container
+    fn set_valign(&self, align: Align) {
+        self.common().valign.set(align);
+        request_relayout();
+    }
+
+    fn get_valign(&self) -> Align {
+        self.common().valign.get()
+    }
+
+    /// If hexpand is true, the container gives extra horizontal space to this widget
+    fn set_hexpand(&self, expand: bool) {
+        self.common().hexpand.set(expand);
+        request_relayout();
+    }
+
+    fn get_hexpand(&self) -> bool {
+        self.common().hexpand.get()
+    }
+
+    /// If vexpand is true, the container gives extra vertical space to this widget
+    fn set_vexpand(&self, expand: bool) {
+        self.common().vexpand.set(expand);
+        request_relayout();
+    }
+
+    fn get_vexpand(&self) -> bool {
+        self.common().vexpand.get()
+    }
+
+    /// Hidden widgets are not drawn, take no space in boxes and receive no events
+    fn set_visible(&self, visible: bool) {
+        if !visible {
+            self.cancel_input();
+        }
+        self.common().visible.set(visible);
+        request_relayout();
+    }
+
+    fn get_visible(&self) -> bool {
+        self.common().visible.get()
+    }
+
+    /// Insensitive widgets and their descendants ignore input and are greyed out
+    fn set_sensitive(&self, sensitive: bool) {
+        if !sensitive {
+            self.cancel_input();
+        }
+        self.common().sensitive.set(sensitive);
+        request_redraw();
+    }
+
+    fn get_sensitive(&self) -> bool {
+        self.common().sensitive.get()
+    }
+
+    /// Make this widget receive key events.
+    /// The focus moves after the current event has been sent to all widgets.
+    fn grab_focus(&self) {
+        self.common().focus_requested.set(true);
+        request_focus();
+    }
+
+    fn has_focus(&self) -> bool {
+        self.common().focused.get()
+    }
+
+    /// Set the minimum size. None keeps the size the widget requests.
+    fn set_min_size(&self, width: Option<u32>, height: Option<u32>) {
+        self.common().min_width.set(width);
+        self.common().min_height.set(height);
+        request_relayout();
+    }
+
+    fn get_min_size(&self) -> (Option<u32>, Option<u32>) {
+        (self.common().min_width.get(), self.common().min_height.get())
+    }
+
+    /// Set the maximum size. A larger allocation is shrunk and the widget is placed by its alignment.
+    fn set_max_size(&self, width: Option<u32>, height: Option<u32>) {
+        self.common().max_width.set(width);
+        self.common().max_height.set(height);
+        request_relayout();
+    }
+
+    fn get_max_size(&self) -> (Option<u32>, Option<u32>) {
+        (self.common().max_width.get(), self.common().max_height.get())
+    }
+
+    /// Make the widget exactly this size
+    fn set_fixed_size(&self, width: u32, height: u32) {
+        self.set_min_size(Some(width), Some(height));
+        self.set_max_size(Some(width), Some(height));
+    }
+
+    /// Keep width / height of the allocation by shrinking one side
+    fn set_aspect_ratio(&self, ratio: Option<f64>) {
+        self.common().aspect_ratio.set(ratio);
+        request_relayout();
+    }
+
+    fn get_aspect_ratio(&self) -> Option<f64> {
+        self.common().aspect_ratio.get()
+    }
+
+    /// The rect allocated by the container, if it has been laid out
+    fn get_allocation(&self) -> Option<Rect> {
+        self.common().allocation.get()
+    }
+
+    /// Return minimum and natural width of this widget
+    fn preferred_width(&self) -> SizeRange {
+        SizeRange::default()
+    }
+
+    /// Return minimum and natural height of this widget when it is given the width.
+    /// Widgets whose height depends on their width (e.g. wrapped text) override this.
+    fn preferred_height_for_width(&self, _width: u32) -> SizeRange {
+        SizeRange::default()
+    }
+
+    /// Return adequate size for this widget.
+    /// This is the natural width and the natural height for that width.
+    fn requested_size(&self) -> (u32, u32) {
+        let width = self.preferred_width().natural;
+        (width, self.preferred_height_for_width(width).natural)
     }
 }
 
 /// Widget which can contain other widgets 
 pub trait ContainerTrait: WidgetTrait {
     fn with_children<F: FnMut(&AnyWidget)>(&self, f: F);
+
+    /// Return a copy of the list of children.
+    /// Iterate over this instead of with_children when callbacks may add or remove children.
+    fn children(&self) -> Vec<AnyWidget> {
+        let mut children = Vec::new();
+        self.with_children(|w| children.push(w.clone()));
+        children
+    }
+
+    /// True if this widget or one of its descendants has the keyboard focus
+    fn contains_focus(&self) -> bool {
+        self.has_focus() || self.children().iter().any(|w| w.contains_focus())
+    }
 }
 
 /// Container widget with one child
@@ -12,7 +12,7 @@ use ::sdl2::keyboard::Keycode;
 use ::sdl2::video::Window as SdlWindow;
 
 use prelude::*;
-use ::widgets::Frame;
+use ::widgets::{Frame, AnyWidgetWeakRef};
 use ::theme::ThemeLoader;
 
 struct UIContext {
@@ -38,18 +38,22 @@ impl UIContext {
     /// Start main loop
     pub fn main_loop(&self) {
         let mut event_pump = self.sdl_context.event_pump().unwrap();
+        let mouse = self.sdl_context.mouse();
         let fps_duration = Duration::from_millis(1000 / 20);
-        let frames = self.frames.borrow();
         let mut prev_instant: Instant = self.prev_instant;
 
-        let send_event = |event: Event| {
-            frames[0].event_handler(&event);
-        };
-
         request_redraw();
         
         'running: loop {
             for sdl_event in event_pump.poll_iter() {
+                // Clone the list because callbacks may open or close frames
+                let frames = self.frames.borrow().clone();
+                let send_event = |window_id: u32, event: Event| {
+                    if let Some(frame) = frames.iter().find(|f| f.window_id() == window_id) {
+                        frame.event_handler(&event);
+                    }
+                };
+
                 match sdl_event {
                     SdlEvent::Quit {..}  => {
                         break 'running
@@ -57,27 +61,67 @@ impl UIContext {
                     SdlEvent::KeyDown { keycode: Some(Keycode::Escape), .. } => {
                         
                     },
-                    SdlEvent::MouseButtonDown {x, y, mouse_btn, .. } => {
-                        send_event(Event::ButtonDown((x, y), mouse_btn));
+                    SdlEvent::KeyDown { window_id, keycode: Some(keycode), keymod, .. } => {
+                        send_event(window_id, Event::KeyDown(keycode, keymod));
+                    },
+                    SdlEvent::TextInput { window_id, text, .. } => {
+                        send_event(window_id, Event::TextInput(text));
+                    },
+                    SdlEvent::MouseButtonDown { window_id, x, y, mouse_btn, .. } => {
+                        send_event(window_id, Event::ButtonDown((x, y), mouse_btn));
                     },
-                    SdlEvent::MouseButtonUp {x, y, mouse_btn, .. } => {
-                        send_event(Event::ButtonUp((x, y), mouse_btn));
+                    SdlEvent::MouseButtonUp { window_id, x, y, mouse_btn, .. } => {
+                        send_event(window_id, Event::ButtonUp((x, y), mouse_btn));
                     },
-                    SdlEvent::Window {win_event_id, .. } => {
+                    SdlEvent::MouseMotion { window_id, x, y, .. } => {
+                        send_event(window_id, Event::MouseMotion((x, y)));
+                    },
+                    SdlEvent::MouseWheel { window_id, x, y, .. } => {
+                        let (_, pointer_x, pointer_y) = mouse.mouse_state();
+                        WHEEL_CLAIMED.with(|a| a.set(false));
+                        send_event(window_id, Event::MouseWheel((pointer_x, pointer_y), (x, y)));
+                    },
+                    SdlEvent::Window { window_id, win_event_id, .. } => {
                         match win_event_id {
                             WindowEventId::FocusLost => {
-                                send_event(Event::WindowFocusLost);
+                                send_event(window_id, Event::WindowFocusLost);
                             },
                             WindowEventId::SizeChanged => {
-                                request_redraw();
-                                frames[0].size_update();
-                            }
+                                request_relayout();
+                            },
+                            WindowEventId::Close => {
+                                // Closing the first frame quits. Other frames are just closed.
+                                match frames.iter().position(|f| f.window_id() == window_id) {
+                                    Some(0) => { break 'running },
+                                    Some(i) => { frames[i].close(); },
+                                    None => (),
+                                }
+                            },
                             _ => {},
                         }
                     }
                     _ => {}
                 }
-                
+
+                if focus_request_start() {
+                    for frame in frames.iter() {
+                        frame.update_focus();
+                    }
+                }
+            }
+
+            run_timeouts();
+            if focus_request_start() {
+                for frame in self.frames.borrow().clone().iter() {
+                    frame.update_focus();
+                }
+            }
+
+            let frames = self.frames.borrow().clone();
+            if relayout_start() {
+                for frame in frames.iter() {
+                    frame.size_update();
+                }
             }
 
             if redraw_start() {
@@ -102,6 +146,13 @@ impl UIContext {
         let mut frames = self.frames.borrow_mut();
         frames.push(frame);
     }
+
+    /// Remove the window from the list of frames
+    pub fn remove_frame(&self, frame: &Frame) {
+        let mut frames = self.frames.borrow_mut();
+        let p: *const Frame = frame;
+        frames.retain(|f| &**f as *const Frame != p);
+    }
 }
 
 thread_local!(static UI_CONTEXT: RefCell<Option<UIContext>> = RefCell::new(None));
@@ -159,6 +210,18 @@ pub fn add_frame(frame: Rc<Frame>) {
     });
 }
 
+/// Stop sending events to the frame and drawing it
+pub fn remove_frame(frame: &Frame) {
+    UI_CONTEXT.with(|uicontext| {
+        match *uicontext.borrow() {
+            Some(ref uicontext) => {
+                uicontext.remove_frame(frame);
+            },
+            None => { panic!("Removing frame before uicontext initialization"); },
+        }
+    });
+}
+
 // If redrawing is needed, set true
 thread_local!(static REDRAW_REQUESTED: Cell<bool> = Cell::new(false));
 
@@ -174,3 +237,106 @@ fn redraw_start() -> bool {
     })
 }
 
+// If a widget has already scrolled by the current wheel event, set true
+thread_local!(static WHEEL_CLAIMED: Cell<bool> = Cell::new(false));
+
+/// Take the current mouse wheel event. False if another widget has taken it,
+/// so that nested scrollable widgets don't scroll together.
+/// Containers pass the wheel event to their children before claiming it.
+pub fn claim_wheel() -> bool {
+    WHEEL_CLAIMED.with(|a| !a.replace(true))
+}
+
+// If sizes or positions of widgets need to be recalculated, set true
+thread_local!(static RELAYOUT_REQUESTED: Cell<bool> = Cell::new(false));
+
+/// Reallocate rects of all widgets before next drawing
+pub fn request_relayout() {
+    RELAYOUT_REQUESTED.with(|a| a.set(true));
+    request_redraw();
+}
+
+fn relayout_start() -> bool {
+    RELAYOUT_REQUESTED.with(|a| {
+        let is_requested = a.get();
+        a.set(false);
+        is_requested
+    })
+}
+
+// Functions called from the main loop after their time
+thread_local!(static TIMEOUTS: RefCell<Vec<(Instant, Box<FnMut() + 'static>)>> = RefCell::new(Vec::new()));
+
+/// Call the function once from the main loop after the delay.
+/// The accuracy is limited by the frame rate of the main loop.
+pub fn add_timeout<F>(delay: Duration, f: F) where F: FnOnce() + 'static {
+    let mut f = Some(f);
+    let deadline = Instant::now() + delay;
+    TIMEOUTS.with(|t| t.borrow_mut().push((deadline, Box::new(move || {
+        if let Some(f) = f.take() { f(); }
+    }))));
+}
+
+fn run_timeouts() {
+    let now = Instant::now();
+    // Take the due functions out first because they may add new timeouts
+    let due: Vec<(Instant, Box<FnMut()>)> = TIMEOUTS.with(|t| {
+        let mut t = t.borrow_mut();
+        let (due, waiting) = t.drain(..).partition(|&(deadline, _)| deadline <= now);
+        *t = waiting;
+        due
+    });
+    for (_, mut f) in due {
+        f();
+    }
+}
+
+// The widget receiving key events
+thread_local!(static FOCUS: RefCell<Option<AnyWidgetWeakRef>> = RefCell::new(None));
+
+// If a widget called grab_focus, set true
+thread_local!(static FOCUS_REQUESTED: Cell<bool> = Cell::new(false));
+
+// Widgets from the frame to the focused widget while a key event is sent.
+// None while other events are sent.
+thread_local!(static FOCUS_PATH: RefCell<Option<Vec<AnyWidget>>> = RefCell::new(None));
+
+/// Give the keyboard focus to the widget. None takes it from the focused widget.
+pub fn set_focus(widget: Option<&AnyWidget>) {
+    if let Some(old) = get_focus() {
+        old.common().focused.set(false);
+    }
+    if let Some(w) = widget {
+        w.common().focused.set(true);
+    }
+    FOCUS.with(|f| *f.borrow_mut() = widget.map(|w| w.weak_ref()));
+    request_redraw();
+}
+
+/// The widget which has the keyboard focus
+pub fn get_focus() -> Option<AnyWidget> {
+    FOCUS.with(|f| f.borrow().as_ref().and_then(|w| w.upgrade()))
+}
+
+/// Make frames look for the widget which called grab_focus
+pub fn request_focus() {
+    FOCUS_REQUESTED.with(|a| a.set(true));
+}
+
+fn focus_request_start() -> bool {
+    FOCUS_REQUESTED.with(|a| {
+        let is_requested = a.get();
+        a.set(false);
+        is_requested
+    })
+}
+
+/// Set the widgets which receive the current key event
+pub fn set_focus_path(path: Option<Vec<AnyWidget>>) {
+    FOCUS_PATH.with(|p| *p.borrow_mut() = path);
+}
+
+/// True if the widget receives the current key event
+pub fn on_focus_path(widget: &AnyWidget) -> bool {
+    FOCUS_PATH.with(|p| p.borrow().as_ref().map_or(true, |path| path.contains(widget)))
+}
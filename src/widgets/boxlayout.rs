@@ -0,0 +1,124 @@
+
+use prelude::*;
+use enums::{PackType, Direction};
+
+/// A child of VBox or HBox with its packing options
+pub struct BoxChild {
+    pub widget: AnyWidget,
+    pub expand: bool,
+    pub fill: bool,
+    pub padding: u32,
+    pub pack_type: PackType,
+}
+
+impl BoxChild {
+    /// The child is expanded if it is packed with expand or it has the expand hint
+    pub fn expands(&self, direction: Direction) -> bool {
+        self.expand || match direction {
+            Direction::Horizon => self.widget.get_hexpand(),
+            Direction::Vertical => self.widget.get_vexpand(),
+        }
+    }
+}
+
+/// Calculate the size of a box along its main axis from sizes of children.
+/// Hidden children take no space.
+pub fn main_axis_size(children: &[BoxChild], sizes: &[SizeRange], spacing: u32, homogeneous: bool)
+                      -> SizeRange {
+    let visible: Vec<(&BoxChild, &SizeRange)> = children.iter().zip(sizes.iter())
+        .filter(|&(c, _)| c.widget.get_visible()).collect();
+    let n = visible.len() as u32;
+    if n == 0 {
+        return SizeRange::default();
+    }
+
+    let sum_spacing = spacing * (n - 1);
+    let slots = visible.iter().map(|&(c, s)| s.add(c.padding * 2));
+
+    if homogeneous {
+        let largest = slots.fold(SizeRange::default(), |a, s| a.max(s));
+        SizeRange::new(largest.minimum * n, largest.natural * n).add(sum_spacing)
+    }else{
+        slots.fold(SizeRange::default(), |a, s| a.sum(s)).add(sum_spacing)
+    }
+}
+
+/// Calculate positions of children along the main axis of a box.
+/// `sizes` are requested sizes of children, and the result is a pair of
+/// an offset from the start of the box and an allocated size for each child.
+///
+/// If the box is smaller than the natural size, children are shrunk towards their minimum sizes.
+/// The space exceeding the natural size is shared by expanded children.
+/// Hidden children get no space.
+pub fn distribute(children: &[BoxChild], sizes: &[SizeRange], length: u32, spacing: u32,
+                  homogeneous: bool, direction: Direction) -> Vec<(i32, u32)> {
+    let n = children.iter().filter(|c| c.widget.get_visible()).count() as u32;
+    if n == 0 {
+        return Vec::new();
+    }
+
+    let sum_spacing = spacing * (n - 1);
+
+    // Sizes of the slot for each child including padding
+    let slots: Vec<u32> = if homogeneous {
+        let available = if length > sum_spacing { length - sum_spacing }else{ 0 };
+        let slot = available / n;
+        children.iter().map(|c| if c.widget.get_visible() { slot }else{ 0 }).collect()
+    }else{
+        let total = main_axis_size(children, sizes, spacing, false);
+        let n_expand = children.iter()
+            .filter(|c| c.widget.get_visible() && c.expands(direction)).count() as u32;
+
+        children.iter().zip(sizes.iter()).map(|(c, s)| {
+            let padding = c.padding * 2;
+            if !c.widget.get_visible() {
+                0
+            }else if length <= total.minimum {
+                s.minimum + padding
+            }else if length < total.natural {
+                // Shrink children in proportion to the difference of natural and minimum size
+                let extra = (length - total.minimum) as u64;
+                let gap = (s.natural - s.minimum) as u64;
+                let total_gap = (total.natural - total.minimum) as u64;
+                s.minimum + padding + (gap * extra / total_gap) as u32
+            }else if c.expands(direction) {
+                s.natural + padding + (length - total.natural) / n_expand
+            }else{
+                s.natural + padding
+            }
+        }).collect()
+    };
+
+    let mut result = vec![(0, 0); children.len()];
+    let mut start = 0i32;
+    let mut end = length as i32;
+
+    for (i, c) in children.iter().enumerate() {
+        if !c.widget.get_visible() {
+            continue;
+        }
+        let slot = slots[i];
+        let slot_start = match c.pack_type {
+            PackType::Start => {
+                let s = start;
+                start += (slot + spacing) as i32;
+                s
+            },
+            PackType::End => {
+                end -= slot as i32;
+                let s = end;
+                end -= spacing as i32;
+                s
+            },
+        };
+
+        // Padding is not applied if the slot is smaller than it
+        let padding = if slot < c.padding * 2 { 0 }else{ c.padding };
+        let inner = slot - padding * 2;
+        let size = if c.fill || inner < sizes[i].natural { inner }else{ sizes[i].natural };
+        let offset = slot_start + padding as i32 + (inner - size) as i32 / 2;
+        result[i] = (offset, size);
+    }
+    
+    result
+}
@@ -10,6 +10,7 @@ use uicontext::request_redraw;
 const PADDING_SIZE: u32 = 3;
 
 pub struct Button {
+    common: WidgetCommon,
     rect: Cell<Option<Rect>>,
     child: RefCell<Option<AnyWidget>>,
     callback_clicked: RefCell<Option<Box<Fn() + 'static>>>,
@@ -19,6 +20,7 @@ pub struct Button {
 impl Button {
     pub fn new() -> Rc<Button> {
         Rc::new(Button{
+            common: WidgetCommon::new(),
             rect: Cell::new(None),
             child: RefCell::new(None),
             callback_clicked: RefCell::new(None),
@@ -28,6 +30,7 @@ impl Button {
 
     pub fn new_with_label(s: &str) -> Rc<Button> {
         Rc::new(Button{
+            common: WidgetCommon::new(),
             rect: Cell::new(None),
             child: RefCell::new(Some(::widgets::Label::new(s).into())),
             callback_clicked: RefCell::new(None),
@@ -37,6 +40,10 @@ impl Button {
 }
 
 impl WidgetTrait for Button {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
     fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
         let rect = self.rect.get().expect("No rectangle allocation at rendering");
         let color_theme = ::theme::get_theme_color();
@@ -137,27 +144,31 @@ impl WidgetTrait for Button {
                 }
             },
             Event::WindowFocusLost => {
-                if self.state.get() != ButtonState::None {
-                    self.state.set(ButtonState::None);
-                    request_redraw();
-                }
+                self.cancel_input();
             },
             _ => (),
         }
     }
 
-    fn requested_size(&self) -> Option<(u32, u32)> {
-        let child = self.child.borrow();
-        
-        Some(if let Some(ref c) = *child {
-            if let Some(size) = c.requested_size() {
-                (size.0 + PADDING_SIZE * 2, size.1 + PADDING_SIZE * 2)
-            }else{
-                (PADDING_SIZE * 2, PADDING_SIZE * 2)
-            }
-        }else{
-            (PADDING_SIZE * 2, PADDING_SIZE * 2)
-        })
+    fn cancel_input(&self) {
+        if self.state.get() != ButtonState::None {
+            self.state.set(ButtonState::None);
+            request_redraw();
+        }
+        for c in self.children() {
+            c.cancel_input();
+        }
+    }
+
+    fn preferred_width(&self) -> SizeRange {
+        let size = self.with_child(|c| c.preferred_width()).unwrap_or_default();
+        size.add(PADDING_SIZE * 2)
+    }
+
+    fn preferred_height_for_width(&self, width: u32) -> SizeRange {
+        let child_width = if width > PADDING_SIZE * 2 { width - PADDING_SIZE * 2 }else{ 0 };
+        let size = self.with_child(|c| c.preferred_height_for_width(child_width)).unwrap_or_default();
+        size.add(PADDING_SIZE * 2)
     }
 }
 
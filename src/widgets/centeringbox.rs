@@ -2,9 +2,11 @@ use std::rc::Rc;
 use std::cell::{RefCell, Cell};
 
 use prelude::*;
+use uicontext::request_relayout;
 
 /// The child will be centered on the given rectangler.
 pub struct CenteringBox {
+    common: WidgetCommon,
     rect: Cell<Option<Rect>>,
     child: RefCell<Option<AnyWidget>>,
 }
@@ -12,6 +14,7 @@ pub struct CenteringBox {
 impl CenteringBox {
     pub fn new<W: Into<AnyWidget>>(w: W) -> Rc<CenteringBox> {
         Rc::new(CenteringBox{
+            common: WidgetCommon::new(),
             rect: Cell::new(None),
             child: RefCell::new(Some(w.into())),
         })
@@ -19,13 +22,40 @@ impl CenteringBox {
     
     pub fn empty() -> Rc<CenteringBox> {
         Rc::new(CenteringBox{
+            common: WidgetCommon::new(),
             rect: Cell::new(None),
             child: RefCell::new(None),
         })
     }
+
+    pub fn set_child<W: Into<AnyWidget>>(&self, w: W) {
+        let w = w.into();
+        *self.child.borrow_mut() = Some(w);
+        request_relayout();
+    }
+
+    /// Remove the child. Returns false if the widget is not the child.
+    pub fn remove<W: Into<AnyWidget>>(&self, w: W) -> bool {
+        let w = w.into();
+        let is_child = self.child.borrow().as_ref() == Some(&w);
+        if is_child {
+            self.clear();
+        }
+        is_child
+    }
+
+    /// Remove the child if exists
+    pub fn clear(&self) {
+        *self.child.borrow_mut() = None;
+        request_relayout();
+    }
 }
 
 impl WidgetTrait for CenteringBox {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
     fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
         let child = self.child.borrow();
         if let Some(ref c) = *child {
@@ -39,20 +69,34 @@ impl WidgetTrait for CenteringBox {
         self.rect.set(Some(rect));
 
         if let Some(ref c) = *self.child.borrow() {
-            if let Some(size) = c.requested_size() {
-                let center = rect.center();
-                c.alloc_rect(Rect::from_center(center, size.0, size.1));
-            }else{
-                // Expand the child widget if requested_size returns None.
-                c.alloc_rect(rect);
-            }
+            // Expand the child widget in the direction its natural size is zero.
+            let width = c.preferred_width().natural;
+            let width = if width == 0 || width > rect.width() { rect.width() }else{ width };
+            let height = c.preferred_height_for_width(width).natural;
+            let height = if height == 0 || height > rect.height() { rect.height() }else{ height };
+
+            c.alloc_rect(Rect::from_center(rect.center(), width, height));
         }
     }
 
+    fn preferred_width(&self) -> SizeRange {
+        self.with_child(|c| c.preferred_width()).unwrap_or_default()
+    }
+
+    fn preferred_height_for_width(&self, width: u32) -> SizeRange {
+        self.with_child(|c| c.preferred_height_for_width(width)).unwrap_or_default()
+    }
+
     fn event_handler(&self, event: &Event) {
-        self.with_child(|w| {
+        for w in self.children() {
             w.event_handler(event);
-        });
+        }
+    }
+
+    fn cancel_input(&self) {
+        for c in self.children() {
+            c.cancel_input();
+        }
     }
 }
 
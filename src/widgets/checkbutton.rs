@@ -0,0 +1,237 @@
+use std::rc::Rc;
+use std::cell::{RefCell, Cell};
+use std::cmp::max;
+use ::sdl2::mouse::Mouse;
+use ::sdl2::keyboard::Keycode;
+
+use prelude::*;
+use theme;
+use enums::CheckState;
+use uicontext::{request_redraw, request_relayout};
+use widgets::rendering_insensitive;
+
+const BOX_SIZE: u32 = 13;
+const SPACING: u32 = 4;
+
+/// A check box with a label on its right.
+/// Clicking on the box or the label toggles the state.
+pub struct CheckButton {
+    common: WidgetCommon,
+    rect: Cell<Option<Rect>>,
+    child: RefCell<Option<AnyWidget>>,
+    state: Cell<CheckState>,
+    pressed: Cell<bool>,
+    callback_toggled: RefCell<Option<Rc<Fn(bool) + 'static>>>,
+}
+
+impl CheckButton {
+    pub fn new() -> Rc<CheckButton> {
+        Rc::new(CheckButton{
+            common: WidgetCommon::new(),
+            rect: Cell::new(None),
+            child: RefCell::new(None),
+            state: Cell::new(CheckState::Inactive),
+            pressed: Cell::new(false),
+            callback_toggled: RefCell::new(None),
+        })
+    }
+
+    pub fn new_with_label(s: &str) -> Rc<CheckButton> {
+        let button = CheckButton::new();
+        *button.child.borrow_mut() = Some(::widgets::Label::new(s).into());
+        button
+    }
+
+    /// Set the widget shown on the right of the box
+    pub fn set_child<W: Into<AnyWidget>>(&self, w: W) {
+        *self.child.borrow_mut() = Some(w.into());
+        request_relayout();
+    }
+
+    /// Set the state. The toggled callback is called if the state is changed.
+    pub fn set_state(&self, state: CheckState) {
+        if state == self.state.get() {
+            return;
+        }
+        self.state.set(state);
+        request_redraw();
+        let callback = self.callback_toggled.borrow().clone();
+        if let Some(f) = callback {
+            f(state == CheckState::Active);
+        }
+    }
+
+    pub fn get_state(&self) -> CheckState {
+        self.state.get()
+    }
+
+    pub fn set_active(&self, active: bool) {
+        self.set_state(if active { CheckState::Active }else{ CheckState::Inactive });
+    }
+
+    /// True only if the state is Active
+    pub fn get_active(&self) -> bool {
+        self.state.get() == CheckState::Active
+    }
+
+    /// Set a callback called with the new active state when the state is changed
+    pub fn on_toggled<F>(&self, f: F) where F: Fn(bool) + 'static {
+        *self.callback_toggled.borrow_mut() = Some(Rc::new(f));
+    }
+
+    /// Inconsistent state becomes active by toggling
+    fn toggle(&self) {
+        self.set_active(!self.get_active());
+    }
+
+    fn box_rect(&self, rect: Rect) -> Rect {
+        let y = rect.y() + (rect.height() as i32 - BOX_SIZE as i32) / 2;
+        Rect::new(rect.x(), y, BOX_SIZE, BOX_SIZE)
+    }
+
+    fn render_mark(&self, renderer: &mut Renderer, b: Rect) -> Result<(), String> {
+        renderer.set_draw_color(if rendering_insensitive() {
+            theme::get_theme_color().insensitive_text
+        }else{
+            theme::get_text_color()
+        });
+        let (x, y) = (b.x(), b.y());
+        match self.state.get() {
+            CheckState::Inactive => Ok(()),
+            CheckState::Active => {
+                // A check mark two pixels thick
+                for dy in 0..2 {
+                    try!(renderer.draw_line(Point::new(x + 3, y + 6 + dy), Point::new(x + 5, y + 8 + dy)));
+                    try!(renderer.draw_line(Point::new(x + 5, y + 8 + dy), Point::new(x + 9, y + 4 + dy)));
+                }
+                Ok(())
+            },
+            CheckState::Inconsistent => {
+                renderer.fill_rect(Rect::new(x + 3, y + 5, BOX_SIZE - 6, 3))
+            },
+        }
+    }
+}
+
+impl WidgetTrait for CheckButton {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
+    fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
+        let rect = self.rect.get().expect("No rectangle allocation at rendering");
+        let color = theme::get_theme_color();
+
+        // Sunken box
+        let b = self.box_rect(rect);
+        renderer.set_draw_color(if self.pressed.get() { color.dark_background }else{ color.light_background });
+        try!(renderer.fill_rect(b));
+        renderer.set_draw_color(color.dark_border);
+        try!(renderer.draw_lines(&[
+            Point::new(b.x(), b.bottom() - 1), Point::new(b.x(), b.y()), Point::new(b.right() - 1, b.y()),
+        ]));
+        renderer.set_draw_color(color.light_border);
+        try!(renderer.draw_lines(&[
+            Point::new(b.x() + 1, b.bottom() - 1), Point::new(b.right() - 1, b.bottom() - 1),
+            Point::new(b.right() - 1, b.y() + 1),
+        ]));
+        renderer.set_draw_color(color.dark_border);
+        try!(renderer.draw_lines(&[
+            Point::new(b.x() + 1, b.bottom() - 2), Point::new(b.x() + 1, b.y() + 1),
+            Point::new(b.right() - 2, b.y() + 1),
+        ]));
+        try!(self.render_mark(renderer, b));
+
+        if let Some(ref c) = *self.child.borrow() {
+            try!(c.render(renderer));
+        }
+        Ok(())
+    }
+
+    fn alloc_rect(&self, rect: Rect) {
+        self.rect.set(Some(rect));
+
+        if let Some(ref c) = *self.child.borrow() {
+            let offset = BOX_SIZE + SPACING;
+            let width = if rect.width() > offset { rect.width() - offset }else{ 0 };
+            c.alloc_rect(Rect::new(rect.x() + offset as i32, rect.y(), width, rect.height()));
+        }
+    }
+
+    fn event_handler(&self, event: &Event) {
+        let rect = match self.rect.get() {
+            Some(r) => r,
+            None => { return; },
+        };
+
+        match *event {
+            Event::ButtonDown(p, mouse_btn) => {
+                let inside = rect.contains(p);
+                if inside {
+                    self.grab_focus();
+                }
+                if mouse_btn == Mouse::Left && inside {
+                    self.pressed.set(true);
+                    request_redraw();
+                }
+            },
+            Event::ButtonUp(p, Mouse::Left) => {
+                if self.pressed.get() {
+                    self.pressed.set(false);
+                    request_redraw();
+                    // Releasing outside cancels the click
+                    if rect.contains(p) {
+                        self.toggle();
+                    }
+                }
+            },
+            Event::KeyDown(Keycode::Space, _) if self.has_focus() => {
+                self.toggle();
+            },
+            Event::WindowFocusLost => {
+                self.cancel_input();
+            },
+            _ => (),
+        }
+    }
+
+    fn cancel_input(&self) {
+        if self.pressed.get() {
+            self.pressed.set(false);
+            request_redraw();
+        }
+        for c in self.children() {
+            c.cancel_input();
+        }
+    }
+
+    fn preferred_width(&self) -> SizeRange {
+        match self.with_child(|c| c.preferred_width()) {
+            Some(size) => size.add(BOX_SIZE + SPACING),
+            None => SizeRange::fixed(BOX_SIZE),
+        }
+    }
+
+    fn preferred_height_for_width(&self, width: u32) -> SizeRange {
+        let offset = BOX_SIZE + SPACING;
+        let child_width = if width > offset { width - offset }else{ 0 };
+        let size = self.with_child(|c| c.preferred_height_for_width(child_width)).unwrap_or_default();
+        SizeRange::new(max(size.minimum, BOX_SIZE), max(size.natural, BOX_SIZE))
+    }
+}
+
+impl ContainerTrait for CheckButton {
+    fn with_children<F: FnMut(&AnyWidget)>(&self, f: F) {
+        let mut f = f;
+
+        if let Some(ref c) = *self.child.borrow() {
+            f(c);
+        }
+    }
+}
+
+impl OneChildContainerTrait for CheckButton {
+    fn with_child<F, R>(&self, f: F) -> Option<R> where F: FnOnce(&AnyWidget) -> R {
+        self.child.borrow().as_ref().map(f)
+    }
+}
@@ -0,0 +1,180 @@
+
+use std::cell::Cell;
+use std::cmp::{min, max};
+
+use ::sdl2::render::BlendMode;
+
+use prelude::*;
+use enums::Align;
+
+/// Properties every widget has
+pub struct WidgetCommon {
+    pub halign: Cell<Align>,
+    pub valign: Cell<Align>,
+    pub hexpand: Cell<bool>,
+    pub vexpand: Cell<bool>,
+    /// The rect given by the last alloc_rect through AnyWidget
+    pub allocation: Cell<Option<Rect>>,
+    /// Size overrides. None uses the size the widget requests.
+    pub min_width: Cell<Option<u32>>,
+    pub min_height: Cell<Option<u32>>,
+    pub max_width: Cell<Option<u32>>,
+    pub max_height: Cell<Option<u32>>,
+    /// Width / height kept in the allocation
+    pub aspect_ratio: Cell<Option<f64>>,
+    /// Hidden widgets are not drawn, take no space and receive no events
+    pub visible: Cell<bool>,
+    /// Insensitive widgets and their descendants ignore input and are greyed out
+    pub sensitive: Cell<bool>,
+    /// Set by grab_focus until the frame gives the focus to the widget
+    pub focus_requested: Cell<bool>,
+    /// The widget receives key events
+    pub focused: Cell<bool>,
+}
+
+impl WidgetCommon {
+    pub fn new() -> WidgetCommon {
+        WidgetCommon{
+            halign: Cell::new(Align::Fill),
+            valign: Cell::new(Align::Fill),
+            hexpand: Cell::new(false),
+            vexpand: Cell::new(false),
+            allocation: Cell::new(None),
+            min_width: Cell::new(None),
+            min_height: Cell::new(None),
+            max_width: Cell::new(None),
+            max_height: Cell::new(None),
+            aspect_ratio: Cell::new(None),
+            visible: Cell::new(true),
+            sensitive: Cell::new(true),
+            focus_requested: Cell::new(false),
+            focused: Cell::new(false),
+        }
+    }
+
+    /// Maximum width override, which is never smaller than the minimum width override
+    pub fn max_width_limit(&self) -> Option<u32> {
+        self.max_width.get().map(|m| max(m, self.min_width.get().unwrap_or(0)))
+    }
+
+    /// Maximum height override, which is never smaller than the minimum height override
+    pub fn max_height_limit(&self) -> Option<u32> {
+        self.max_height.get().map(|m| max(m, self.min_height.get().unwrap_or(0)))
+    }
+
+    /// Apply the size overrides to the width the widget requests
+    pub fn constrain_width(&self, size: SizeRange) -> SizeRange {
+        constrain(size, self.min_width.get(), self.max_width.get())
+    }
+
+    /// Apply the size overrides to the height the widget requests
+    pub fn constrain_height(&self, size: SizeRange) -> SizeRange {
+        constrain(size, self.min_height.get(), self.max_height.get())
+    }
+
+    /// Shrink the rect given by the container to the maximum size and the aspect ratio.
+    /// The widget is placed in the rect by its alignment. Fill is treated as Center.
+    pub fn constrain_rect(&self, rect: Rect) -> Rect {
+        let mut width = rect.width();
+        let mut height = rect.height();
+        if let Some(max_width) = self.max_width_limit() {
+            width = min(width, max_width);
+        }
+        if let Some(max_height) = self.max_height_limit() {
+            height = min(height, max_height);
+        }
+        if let Some(ratio) = self.aspect_ratio.get() {
+            if ratio > 0.0 {
+                if width as f64 > height as f64 * ratio {
+                    width = (height as f64 * ratio).round() as u32;
+                }else{
+                    height = (width as f64 / ratio).round() as u32;
+                }
+            }
+        }
+
+        let center = |align| if align == Align::Fill { Align::Center }else{ align };
+        let x = rect.x() + align_offset(center(self.halign.get()), rect.width() - width);
+        let y = rect.y() + align_offset(center(self.valign.get()), rect.height() - height);
+        Rect::new(x, y, width, height)
+    }
+}
+
+// Number of insensitive widgets being rendered
+thread_local!(static INSENSITIVE_DEPTH: Cell<u32> = Cell::new(0));
+
+/// True while an insensitive widget or its descendants are rendered.
+/// Widgets drawing text use the insensitive text color then.
+pub fn rendering_insensitive() -> bool {
+    INSENSITIVE_DEPTH.with(|d| d.get() > 0)
+}
+
+/// Render a widget. If it is insensitive, it is covered with the insensitive color after rendering.
+pub fn render_with_sensitivity<F>(common: &WidgetCommon, renderer: &mut Renderer, f: F)
+                                  -> Result<(), String> where F: FnOnce(&mut Renderer) -> Result<(), String> {
+    if common.sensitive.get() {
+        return f(renderer);
+    }
+
+    INSENSITIVE_DEPTH.with(|d| d.set(d.get() + 1));
+    let result = f(renderer);
+    let depth = INSENSITIVE_DEPTH.with(|d| { d.set(d.get() - 1); d.get() });
+    try!(result);
+
+    // Only the outermost insensitive widget is covered
+    match common.allocation.get() {
+        Some(rect) if depth == 0 => {
+            let blend_mode = renderer.blend_mode();
+            renderer.set_blend_mode(BlendMode::Blend);
+            renderer.set_draw_color(::theme::get_theme_color().insensitive_overlay);
+            let result = renderer.fill_rect(rect);
+            renderer.set_blend_mode(blend_mode);
+            result
+        },
+        _ => Ok(()),
+    }
+}
+
+/// The minimum override wins if it is larger than the maximum override
+fn constrain(size: SizeRange, min_size: Option<u32>, max_size: Option<u32>) -> SizeRange {
+    let mut size = size;
+    if let Some(m) = max_size {
+        size.minimum = min(size.minimum, m);
+        size.natural = min(size.natural, m);
+    }
+    if let Some(m) = min_size {
+        size.minimum = max(size.minimum, m);
+        size.natural = max(size.natural, m);
+    }
+    size
+}
+
+/// Place a widget in the given space by its alignment.
+/// If the alignment is not Fill, the widget is shrunk to its natural size.
+pub fn aligned_rect<W: WidgetTrait + ?Sized>(widget: &W, rect: Rect) -> Rect {
+    let halign = widget.get_halign();
+    let valign = widget.get_valign();
+
+    let width = if halign == Align::Fill {
+        rect.width()
+    }else{
+        ::std::cmp::min(widget.preferred_width().natural, rect.width())
+    };
+    let height = if valign == Align::Fill {
+        rect.height()
+    }else{
+        ::std::cmp::min(widget.preferred_height_for_width(width).natural, rect.height())
+    };
+
+    let x = rect.x() + align_offset(halign, rect.width() - width);
+    let y = rect.y() + align_offset(valign, rect.height() - height);
+    Rect::new(x, y, width, height)
+}
+
+fn align_offset(align: Align, space: u32) -> i32 {
+    match align {
+        Align::Fill | Align::Start => 0,
+        Align::Center => (space / 2) as i32,
+        Align::End => space as i32,
+    }
+}
@@ -0,0 +1,283 @@
+
+use std::rc::Rc;
+use std::cell::{RefCell, Cell};
+
+use ::cassowary::{Solver, Variable, Expression, Constraint, AddConstraintError, RemoveConstraintError};
+use ::cassowary::WeightedRelation::*;
+use ::cassowary::strength::{WEAK, STRONG, REQUIRED};
+
+use prelude::*;
+use uicontext::request_redraw;
+
+/// Variables of the position and size of a widget in ConstraintLayout.
+/// Use them to write constraints, e.g. `button.left |EQ(REQUIRED)| panel.right() + 8.0`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ConstraintVars {
+    pub left: Variable,
+    pub top: Variable,
+    pub width: Variable,
+    pub height: Variable,
+}
+
+impl ConstraintVars {
+    fn new() -> ConstraintVars {
+        ConstraintVars{
+            left: Variable::new(),
+            top: Variable::new(),
+            width: Variable::new(),
+            height: Variable::new(),
+        }
+    }
+
+    pub fn right(&self) -> Expression {
+        self.left + self.width
+    }
+
+    pub fn bottom(&self) -> Expression {
+        self.top + self.height
+    }
+
+    pub fn center_x(&self) -> Expression {
+        self.left + self.width / 2.0
+    }
+
+    pub fn center_y(&self) -> Expression {
+        self.top + self.height / 2.0
+    }
+
+    fn used_in(&self, constraint: &Constraint) -> bool {
+        constraint.expr().terms.iter().any(|t| {
+            t.variable == self.left || t.variable == self.top ||
+                t.variable == self.width || t.variable == self.height
+        })
+    }
+}
+
+/// Container whose children are positioned by linear constraints.
+/// Constraints are solved again whenever the container is resized.
+/// Children prefer their natural sizes with weak strength.
+pub struct ConstraintLayout {
+    common: WidgetCommon,
+    rect: Cell<Option<Rect>>,
+    solver: RefCell<Solver>,
+    vars: ConstraintVars,
+    children: RefCell<Vec<(AnyWidget, ConstraintVars)>>,
+    // Constraints in the solver except the ones of this container itself.
+    // They are removed from the solver with the child whose variables they use.
+    constraints: RefCell<Vec<Constraint>>,
+}
+
+impl ConstraintLayout {
+    pub fn new() -> Rc<ConstraintLayout> {
+        let vars = ConstraintVars::new();
+        let mut solver = Solver::new();
+        
+        solver.add_constraints(&[
+            vars.left |EQ(REQUIRED)| 0.0,
+            vars.top |EQ(REQUIRED)| 0.0,
+        ]).expect("Constraints of ConstraintLayout");
+        solver.add_edit_variable(vars.width, STRONG).expect("Edit variable of ConstraintLayout");
+        solver.add_edit_variable(vars.height, STRONG).expect("Edit variable of ConstraintLayout");
+        
+        Rc::new(ConstraintLayout{
+            common: WidgetCommon::new(),
+            rect: Cell::new(None),
+            solver: RefCell::new(solver),
+            vars: vars,
+            children: RefCell::new(Vec::new()),
+            constraints: RefCell::new(Vec::new()),
+        })
+    }
+
+    /// Variables of this container. The left and top are always 0.
+    pub fn vars(&self) -> ConstraintVars {
+        self.vars
+    }
+
+    /// Add a child and return its variables
+    pub fn add<W: Into<AnyWidget>>(&self, w: W) -> ConstraintVars {
+        let vars = ConstraintVars::new();
+        self.add_to_solver(vars.width |GE(REQUIRED)| 0.0).expect("Constraints of a child");
+        self.add_to_solver(vars.height |GE(REQUIRED)| 0.0).expect("Constraints of a child");
+        {
+            let mut solver = self.solver.borrow_mut();
+            solver.add_edit_variable(vars.width, WEAK).expect("Edit variable of a child");
+            solver.add_edit_variable(vars.height, WEAK).expect("Edit variable of a child");
+        }
+        self.children.borrow_mut().push((w.into(), vars));
+        self.update();
+        vars
+    }
+
+    /// Remove the child and the constraints using its variables.
+    /// Returns false if the widget is not a child of this container.
+    pub fn remove<W: Into<AnyWidget>>(&self, w: W) -> bool {
+        let w = w.into();
+        let removed = {
+            let mut children = self.children.borrow_mut();
+            match children.iter().position(|c| c.0 == w) {
+                Some(i) => Some(children.remove(i).1),
+                None => None,
+            }
+        };
+
+        match removed {
+            Some(vars) => {
+                let used: Vec<Constraint> = {
+                    let mut constraints = self.constraints.borrow_mut();
+                    let (used, rest) = constraints.drain(..).partition(|c| vars.used_in(c));
+                    *constraints = rest;
+                    used
+                };
+                {
+                    let mut solver = self.solver.borrow_mut();
+                    for c in used.iter() {
+                        let _ = solver.remove_constraint(c);
+                    }
+                    let _ = solver.remove_edit_variable(vars.width);
+                    let _ = solver.remove_edit_variable(vars.height);
+                }
+                self.update();
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn get_vars<W: Into<AnyWidget>>(&self, w: W) -> Option<ConstraintVars> {
+        let w = w.into();
+        self.children.borrow().iter().find(|c| c.0 == w).map(|c| c.1)
+    }
+
+    pub fn add_constraint(&self, constraint: Constraint) -> Result<(), AddConstraintError> {
+        try!(self.add_to_solver(constraint));
+        self.update();
+        Ok(())
+    }
+
+    /// Add the constraints in order. If one fails, the constraints before it stay added.
+    pub fn add_constraints(&self, constraints: &[Constraint]) -> Result<(), AddConstraintError> {
+        let mut result = Ok(());
+        for c in constraints {
+            result = self.add_to_solver(c.clone());
+            if result.is_err() {
+                break;
+            }
+        }
+        self.update();
+        result
+    }
+
+    pub fn remove_constraint(&self, constraint: &Constraint) -> Result<(), RemoveConstraintError> {
+        try!(self.solver.borrow_mut().remove_constraint(constraint));
+        self.constraints.borrow_mut().retain(|c| c != constraint);
+        self.update();
+        Ok(())
+    }
+
+    fn add_to_solver(&self, constraint: Constraint) -> Result<(), AddConstraintError> {
+        try!(self.solver.borrow_mut().add_constraint(constraint.clone()));
+        self.constraints.borrow_mut().push(constraint);
+        Ok(())
+    }
+
+    /// Solve constraints with the current size and allocate rects to children
+    fn update(&self) {
+        let rect = match self.rect.get() {
+            Some(rect) => rect,
+            None => { return; },
+        };
+        // Children are not called while the solver is borrowed
+        let children = self.children.borrow().clone();
+        let sizes: Vec<(u32, u32)> = children.iter().map(|&(ref w, _)| {
+            let width = w.preferred_width().natural;
+            (width, w.preferred_height_for_width(width).natural)
+        }).collect();
+
+        let rects: Vec<Rect> = {
+            let mut solver = self.solver.borrow_mut();
+            let _ = solver.suggest_value(self.vars.width, rect.width() as f64);
+            let _ = solver.suggest_value(self.vars.height, rect.height() as f64);
+            for (&(_, ref vars), &(width, height)) in children.iter().zip(sizes.iter()) {
+                let _ = solver.suggest_value(vars.width, width as f64);
+                let _ = solver.suggest_value(vars.height, height as f64);
+            }
+
+            children.iter().map(|&(_, ref vars)| {
+                let value = |v| solver.get_value(v).round();
+                Rect::new(
+                    rect.x() + value(vars.left) as i32, rect.y() + value(vars.top) as i32,
+                    value(vars.width).max(0.0) as u32, value(vars.height).max(0.0) as u32)
+            }).collect()
+        };
+
+        for (&(ref w, _), r) in children.iter().zip(rects) {
+            w.alloc_rect(r);
+        }
+        request_redraw();
+    }
+
+    /// The right-most and bottom-most edges of children in the current solution
+    fn extent(&self) -> (u32, u32) {
+        let solver = self.solver.borrow();
+        let mut extent = (0.0f64, 0.0f64);
+        for &(_, ref vars) in self.children.borrow().iter() {
+            extent.0 = extent.0.max(solver.get_value(vars.left) + solver.get_value(vars.width));
+            extent.1 = extent.1.max(solver.get_value(vars.top) + solver.get_value(vars.height));
+        }
+        (extent.0 as u32, extent.1 as u32)
+    }
+}
+
+impl WidgetTrait for ConstraintLayout {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
+    fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
+        let before_clip = renderer.clip_rect();
+        renderer.set_clip_rect(self.rect.get());
+        
+        for &(ref w, _) in self.children.borrow().iter() {
+            try!(w.render(renderer));
+        }
+
+        renderer.set_clip_rect(before_clip);
+        Ok(())
+    }
+
+    fn alloc_rect(&self, rect: Rect) {
+        self.rect.set(Some(rect));
+        self.update();
+    }
+
+    fn event_handler(&self, event: &Event) {
+        for w in self.children() {
+            w.event_handler(event);
+        }
+    }
+
+    fn cancel_input(&self) {
+        for c in self.children() {
+            c.cancel_input();
+        }
+    }
+
+    fn preferred_width(&self) -> SizeRange {
+        SizeRange::new(0, self.extent().0)
+    }
+
+    fn preferred_height_for_width(&self, _width: u32) -> SizeRange {
+        SizeRange::new(0, self.extent().1)
+    }
+}
+
+impl ContainerTrait for ConstraintLayout {
+    fn with_children<F: FnMut(&AnyWidget)>(&self, f: F) {
+        let mut f = f;
+
+        for &(ref w, _) in self.children.borrow().iter() {
+            f(w);
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Draw allocated rects of widgets over a frame to debug layouts
+
+use ::sdl2::render::BlendMode;
+
+use prelude::*;
+
+// Outline colors cycled by the depth in the tree
+const DEPTH_COLORS: [(u8, u8, u8); 4] = [
+    (0xE0, 0x30, 0x30), (0x20, 0x90, 0x20), (0x30, 0x50, 0xE0), (0xC0, 0x70, 0x00)];
+
+/// Draw outlines, type names and requested sizes of the widget and its descendants.
+/// The deepest widget under the pointer is highlighted.
+pub fn render_debug_overlay(
+    renderer: &mut Renderer, root: &AnyWidget, pointer: Option<(i32, i32)>) -> Result<(), String> {
+
+    let blend_mode = renderer.blend_mode();
+    renderer.set_blend_mode(BlendMode::Blend);
+
+    let mut hovered = None;
+    let mut result = render_widget(renderer, root, 0, pointer, &mut hovered);
+    if result.is_ok() {
+        if let (Some((widget, depth)), Some(pointer)) = (hovered, pointer) {
+            result = render_hovered(renderer, &widget, depth, pointer);
+        }
+    }
+
+    renderer.set_blend_mode(blend_mode);
+    result
+}
+
+/// Mark a widget selected in the inspector
+pub fn render_highlight(renderer: &mut Renderer, rect: Rect) -> Result<(), String> {
+    let blend_mode = renderer.blend_mode();
+    renderer.set_blend_mode(BlendMode::Blend);
+    renderer.set_draw_color(Color::RGBA(0x30, 0x60, 0xF0, 0x50));
+    let mut result = renderer.fill_rect(rect);
+    if result.is_ok() {
+        renderer.set_draw_color(Color::RGBA(0x30, 0x60, 0xF0, 0xFF));
+        result = renderer.draw_rect(rect);
+    }
+    renderer.set_blend_mode(blend_mode);
+    result
+}
+
+fn depth_color(depth: usize, alpha: u8) -> Color {
+    let (r, g, b) = DEPTH_COLORS[depth % DEPTH_COLORS.len()];
+    Color::RGBA(r, g, b, alpha)
+}
+
+fn describe(widget: &AnyWidget) -> String {
+    let (w, h) = widget.requested_size();
+    format!("{} {}x{}", widget.type_name(), w, h)
+}
+
+fn render_widget(
+    renderer: &mut Renderer, widget: &AnyWidget, depth: usize, pointer: Option<(i32, i32)>,
+    hovered: &mut Option<(AnyWidget, usize)>) -> Result<(), String> {
+
+    if !widget.get_visible() {
+        return Ok(());
+    }
+
+    if let Some(rect) = widget.get_allocation() {
+        if let AnyWidget::MarginBox(ref b) = *widget {
+            renderer.set_draw_color(depth_color(depth, 0x40));
+            for band in margin_bands(rect, b.get_margins()) {
+                try!(renderer.fill_rect(band));
+            }
+        }
+
+        renderer.set_draw_color(depth_color(depth, 0xFF));
+        try!(renderer.draw_rect(rect));
+
+        // Names of small widgets are shown only when hovered
+        let text = describe(widget);
+        let (text_w, text_h) = try!(text_size(&text));
+        if text_w + 2 <= rect.width() && text_h + 2 <= rect.height() {
+            try!(render_text(renderer, &text, rect.x() + 1, rect.y() + 1, depth_color(depth, 0xFF)));
+        }
+
+        if let Some(p) = pointer {
+            if rect.contains(p) {
+                *hovered = Some((widget.clone(), depth));
+            }
+        }
+    }
+
+    for c in displayed_children(widget) {
+        try!(render_widget(renderer, &c, depth + 1, pointer, hovered));
+    }
+    Ok(())
+}
+
+/// Children which are drawn now. Hidden pages of a Notebook or a Stack and
+/// the child of a collapsed Expander keep their old allocation, so they are skipped.
+fn displayed_children(widget: &AnyWidget) -> Vec<AnyWidget> {
+    let children = match *widget {
+        AnyWidget::Notebook(ref n) => {
+            let mut children = n.tab_widgets();
+            children.extend(n.current_page_widget());
+            children
+        },
+        AnyWidget::Stack(ref s) => s.get_visible_child().into_iter().collect(),
+        AnyWidget::Expander(ref e) if !e.get_expanded() => vec![e.get_label_widget()],
+        _ => widget.children(),
+    };
+    children.into_iter().filter(|c| c.get_allocation().is_some()).collect()
+}
+
+/// Fill the widget and show its type, rect and requested size near the pointer
+fn render_hovered(
+    renderer: &mut Renderer, widget: &AnyWidget, depth: usize, pointer: (i32, i32))
+    -> Result<(), String> {
+
+    let rect = widget.get_allocation().unwrap();
+    renderer.set_draw_color(depth_color(depth, 0x50));
+    try!(renderer.fill_rect(rect));
+
+    let text = format!("{} at ({}, {}) {}x{}",
+                       describe(widget), rect.x(), rect.y(), rect.width(), rect.height());
+    let (text_w, text_h) = try!(text_size(&text));
+    let (window_w, window_h) = try!(renderer.output_size());
+
+    // Keep the box inside the window
+    let x = ::std::cmp::min(pointer.0 + 12, window_w as i32 - text_w as i32 - 4);
+    let y = ::std::cmp::min(pointer.1 + 12, window_h as i32 - text_h as i32 - 4);
+    let x = ::std::cmp::max(x, 0);
+    let y = ::std::cmp::max(y, 0);
+
+    renderer.set_draw_color(Color::RGBA(0xFF, 0xFF, 0xE0, 0xE0));
+    try!(renderer.fill_rect(Rect::new(x, y, text_w + 4, text_h + 4)));
+    renderer.set_draw_color(depth_color(depth, 0xFF));
+    try!(renderer.draw_rect(Rect::new(x, y, text_w + 4, text_h + 4)));
+    render_text(renderer, &text, x + 2, y + 2, Color::RGB(0, 0, 0))
+}
+
+/// Rects of the margins inside a MarginBox's rect
+fn margin_bands(rect: Rect, margins: (u32, u32, u32, u32)) -> Vec<Rect> {
+    let (top, bottom, left, right) = margins;
+    let (x, y, w, h) = (rect.x(), rect.y(), rect.width(), rect.height());
+    let top = ::std::cmp::min(top, h);
+    let bottom = ::std::cmp::min(bottom, h - top);
+    let left = ::std::cmp::min(left, w);
+    let right = ::std::cmp::min(right, w - left);
+    let inner_h = h - top - bottom;
+
+    let mut bands = Vec::new();
+    if top > 0 { bands.push(Rect::new(x, y, w, top)); }
+    if bottom > 0 { bands.push(Rect::new(x, y + (h - bottom) as i32, w, bottom)); }
+    if inner_h > 0 {
+        if left > 0 { bands.push(Rect::new(x, y + top as i32, left, inner_h)); }
+        if right > 0 { bands.push(Rect::new(x + (w - right) as i32, y + top as i32, right, inner_h)); }
+    }
+    bands
+}
+
+fn text_size(text: &str) -> Result<(u32, u32), String> {
+    ::theme::get_default_font().size_of(text).map_err(|e| e.to_string())
+}
+
+fn render_text(renderer: &mut Renderer, text: &str, x: i32, y: i32, color: Color) -> Result<(), String> {
+    let font = ::theme::get_default_font();
+    let surface = try!(font.render(text).blended(color).map_err(|e| e.to_string()));
+    let texture = try!(renderer.create_texture_from_surface(surface).map_err(|e| e.to_string()));
+    let (w, h) = try!(text_size(text));
+    renderer.copy(&texture, None, Some(Rect::new(x, y, w, h)))
+}
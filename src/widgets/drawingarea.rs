@@ -4,6 +4,7 @@ use std::cell::{RefCell, Cell};
 use prelude::*;
 
 pub struct DrawingArea {
+    common: WidgetCommon,
     rect: Cell<Option<Rect>>,
     callback_draw: RefCell<Option<Box<Fn(&mut Renderer) -> Result<(), String> + 'static>>>,
 }
@@ -11,6 +12,7 @@ pub struct DrawingArea {
 impl DrawingArea {
     pub fn new() -> Rc<DrawingArea> {
         Rc::new(DrawingArea{
+            common: WidgetCommon::new(),
             rect: Cell::new(None),
             callback_draw: RefCell::new(None),
         })
@@ -22,6 +24,10 @@ impl DrawingArea {
 }
 
 impl WidgetTrait for DrawingArea {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
     fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
         let callback_draw = self.callback_draw.borrow();
         let rect = self.rect.get().expect("No rectangle allocation at rendering");
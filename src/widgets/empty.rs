@@ -4,18 +4,24 @@ use std::cell::Cell;
 use prelude::*;
 
 pub struct Empty {
+    common: WidgetCommon,
     rect: Cell<Option<Rect>>,
 }
 
 impl Empty {
     pub fn new() -> Rc<Empty> {
         Rc::new(Empty{
+            common: WidgetCommon::new(),
             rect: Cell::new(None),
         })
     }
 }
 
 impl WidgetTrait for Empty {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
     fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
         renderer.set_draw_color(::sdl2::pixels::Color::RGB(0, 0, 0xFF)/*get_background_color()*/);
         try!(renderer.fill_rect(self.rect.get().unwrap()));
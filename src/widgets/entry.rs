@@ -0,0 +1,437 @@
+use std::rc::Rc;
+use std::cell::{RefCell, Cell};
+use std::cmp::{min, max};
+use ::sdl2::mouse::Mouse;
+use ::sdl2::keyboard::{Keycode, Mod};
+
+use prelude::*;
+use theme;
+use event::{ctrl_pressed, shift_pressed};
+use uicontext::request_redraw;
+use widgets::rendering_insensitive;
+use super::textutil;
+
+const PADDING: u32 = 3;
+const NATURAL_WIDTH: u32 = 150;
+
+fn text_width(s: &str) -> u32 {
+    if s.is_empty() {
+        return 0;
+    }
+    theme::get_default_font().size_of(s).map(|size| size.0).unwrap_or(0)
+}
+
+/// Single line text input
+pub struct Entry {
+    common: WidgetCommon,
+    rect: Cell<Option<Rect>>,
+    text: RefCell<String>,
+    placeholder: RefCell<String>,
+    max_length: Cell<Option<usize>>,
+    caret: Cell<usize>, // Byte index in the text
+    anchor: Cell<Option<usize>>, // The other end of the selection
+    scroll: Cell<u32>, // Width of the text hidden at the left
+    dragging: Cell<bool>,
+    callback_changed: RefCell<Option<Box<Fn(&str) + 'static>>>,
+    callback_activate: RefCell<Option<Box<Fn() + 'static>>>,
+}
+
+impl Entry {
+    pub fn new() -> Rc<Entry> {
+        Rc::new(Entry{
+            common: WidgetCommon::new(),
+            rect: Cell::new(None),
+            text: RefCell::new(String::new()),
+            placeholder: RefCell::new(String::new()),
+            max_length: Cell::new(None),
+            caret: Cell::new(0),
+            anchor: Cell::new(None),
+            scroll: Cell::new(0),
+            dragging: Cell::new(false),
+            callback_changed: RefCell::new(None),
+            callback_activate: RefCell::new(None),
+        })
+    }
+
+    /// Set the text shown in gray while the entry is empty
+    pub fn set_placeholder(&self, placeholder: &str) {
+        *self.placeholder.borrow_mut() = placeholder.to_owned();
+        request_redraw();
+    }
+
+    pub fn get_placeholder(&self) -> String {
+        self.placeholder.borrow().clone()
+    }
+
+    /// Limit the number of characters. The current text is cut if it is longer.
+    pub fn set_max_length(&self, max_length: Option<usize>) {
+        self.max_length.set(max_length);
+        let text = self.get_text();
+        let cut = self.fit_length(&text, 0);
+        if cut < text.len() {
+            self.replace(0, text.len(), &text[..cut]);
+        }
+    }
+
+    pub fn get_max_length(&self) -> Option<usize> {
+        self.max_length.get()
+    }
+
+    /// Byte index of the caret
+    pub fn get_caret(&self) -> usize {
+        self.caret.get()
+    }
+
+    /// Move the caret and clear the selection. The index is moved to a grapheme boundary.
+    pub fn set_caret(&self, caret: usize) {
+        let caret = self.boundary_at(caret);
+        self.caret.set(caret);
+        self.anchor.set(None);
+        request_redraw();
+    }
+
+    /// Byte range of the selected text
+    pub fn get_selection(&self) -> Option<(usize, usize)> {
+        let caret = self.caret.get();
+        match self.anchor.get() {
+            Some(a) if a != caret => Some((min(a, caret), max(a, caret))),
+            _ => None,
+        }
+    }
+
+    /// Select a byte range. The caret is put at the end.
+    pub fn select_region(&self, start: usize, end: usize) {
+        self.anchor.set(Some(self.boundary_at(start)));
+        self.caret.set(self.boundary_at(end));
+        request_redraw();
+    }
+
+    pub fn select_all(&self) {
+        let len = self.text.borrow().len();
+        self.select_region(0, len);
+    }
+
+    /// Set a callback called with the new text when the text is changed
+    pub fn on_changed<F>(&self, f: F) where F: Fn(&str) + 'static {
+        *self.callback_changed.borrow_mut() = Some(Box::new(f));
+    }
+
+    /// Set a callback called when Enter is pressed
+    pub fn on_activate<F>(&self, f: F) where F: Fn() + 'static {
+        *self.callback_activate.borrow_mut() = Some(Box::new(f));
+    }
+
+    /// The nearest grapheme boundary at or before the index
+    fn boundary_at(&self, index: usize) -> usize {
+        let text = self.text.borrow();
+        let index = min(index, text.len());
+        if index == text.len() {
+            return index;
+        }
+        *textutil::boundaries(&text).iter().take_while(|b| **b <= index).last().unwrap()
+    }
+
+    /// Length in bytes of the longest head of s which fits in max_length
+    /// when `kept` characters stay in the text.
+    /// The head ends at a grapheme boundary.
+    fn fit_length(&self, s: &str, kept: usize) -> usize {
+        let max_length = match self.max_length.get() {
+            Some(m) => m,
+            None => { return s.len(); },
+        };
+        let cut = textutil::char_index(s, max_length.saturating_sub(kept));
+        if cut == s.len() {
+            return cut;
+        }
+        *textutil::boundaries(s).iter().take_while(|b| **b <= cut).last().unwrap()
+    }
+
+    /// Replace the byte range with the string and put the caret after it
+    fn replace(&self, start: usize, end: usize, s: &str) {
+        if start == end && s.is_empty() {
+            return;
+        }
+        self.text.borrow_mut().replace_range(start..end, s);
+        self.caret.set(start + s.len());
+        self.anchor.set(None);
+        request_redraw();
+
+        let text = self.get_text();
+        if let Some(ref f) = *self.callback_changed.borrow() {
+            f(&text);
+        }
+    }
+
+    /// Insert typed text in place of the selection
+    fn insert(&self, s: &str) {
+        let (start, end) = self.get_selection().unwrap_or((self.caret.get(), self.caret.get()));
+        let kept = {
+            let text = self.text.borrow();
+            text[..start].chars().count() + text[end..].chars().count()
+        };
+        let s = &s[..self.fit_length(s, kept)];
+        if !s.is_empty() || start != end {
+            self.replace(start, end, s);
+        }
+    }
+
+    /// Delete the selection, or the text between the caret and the given position
+    fn delete_to(&self, pos: usize) {
+        let caret = self.caret.get();
+        let (start, end) = self.get_selection().unwrap_or((min(caret, pos), max(caret, pos)));
+        self.replace(start, end, "");
+    }
+
+    /// Move the caret. With extend, the selection is extended to the new position.
+    fn move_caret(&self, pos: usize, extend: bool) {
+        if extend {
+            if self.anchor.get().is_none() {
+                self.anchor.set(Some(self.caret.get()));
+            }
+        }else{
+            self.anchor.set(None);
+        }
+        self.caret.set(pos);
+        request_redraw();
+    }
+
+    /// Position the caret moves to by an arrow key
+    fn arrow_target(&self, forward: bool, word: bool) -> usize {
+        let text = self.text.borrow();
+        let caret = self.caret.get();
+        match (forward, word) {
+            (false, false) => textutil::prev_boundary(&text, caret),
+            (true, false) => textutil::next_boundary(&text, caret),
+            (false, true) => textutil::prev_word_start(&text, caret),
+            (true, true) => textutil::next_word_end(&text, caret),
+        }
+    }
+
+    fn text_rect(&self, rect: Rect) -> Rect {
+        Rect::new(rect.x() + PADDING as i32, rect.y() + PADDING as i32,
+                  rect.width().saturating_sub(PADDING * 2), rect.height().saturating_sub(PADDING * 2))
+    }
+
+    /// The grapheme boundary nearest to x
+    fn index_at(&self, x: i32) -> usize {
+        let rect = match self.rect.get() {
+            Some(r) => self.text_rect(r),
+            None => { return 0; },
+        };
+        let x = x - rect.x() + self.scroll.get() as i32;
+        let text = self.text.borrow();
+        let mut result = 0;
+        let mut prev_x = 0;
+        for b in textutil::boundaries(&text) {
+            let b_x = text_width(&text[..b]) as i32;
+            if b_x > x {
+                // Pick the closer of this and the previous boundary
+                if b_x - x < x - prev_x {
+                    result = b;
+                }
+                break;
+            }
+            result = b;
+            prev_x = b_x;
+        }
+        result
+    }
+
+    /// Scroll the text so that the caret is visible
+    fn scroll_to_caret(&self, width: u32) {
+        let text = self.text.borrow();
+        let caret_x = text_width(&text[..self.caret.get()]);
+        let text_w = text_width(&text);
+        let mut scroll = self.scroll.get();
+        if caret_x < scroll {
+            scroll = caret_x;
+        }else if caret_x > scroll + width {
+            scroll = caret_x - width;
+        }
+        // Do not leave empty space at the right of scrolled text
+        scroll = min(scroll, text_w.saturating_sub(width));
+        self.scroll.set(scroll);
+    }
+
+    fn key_down(&self, keycode: Keycode, keymod: Mod) {
+        let shift = shift_pressed(keymod);
+        let ctrl = ctrl_pressed(keymod);
+        let len = self.text.borrow().len();
+
+        match keycode {
+            Keycode::Left | Keycode::Right => {
+                let forward = keycode == Keycode::Right;
+                match self.get_selection() {
+                    // Without Shift, the caret goes to the edge of the selection
+                    Some((start, end)) if !shift => {
+                        self.move_caret(if forward { end }else{ start }, false);
+                    },
+                    _ => {
+                        let target = self.arrow_target(forward, ctrl);
+                        self.move_caret(target, shift);
+                    },
+                }
+            },
+            Keycode::Home => self.move_caret(0, shift),
+            Keycode::End => self.move_caret(len, shift),
+            Keycode::Backspace => {
+                let target = self.arrow_target(false, ctrl);
+                self.delete_to(target);
+            },
+            Keycode::Delete => {
+                let target = self.arrow_target(true, ctrl);
+                self.delete_to(target);
+            },
+            Keycode::A if ctrl => self.select_all(),
+            Keycode::Return | Keycode::KpEnter => {
+                if let Some(ref f) = *self.callback_activate.borrow() {
+                    f();
+                }
+            },
+            _ => (),
+        }
+    }
+
+    fn render_text(&self, renderer: &mut Renderer, text_rect: Rect) -> Result<(), String> {
+        let color = theme::get_theme_color();
+        let font = theme::get_default_font();
+        let text = self.text.borrow();
+        let x = text_rect.x() - self.scroll.get() as i32;
+        let height = font.height() as u32;
+        let y = text_rect.y() + (text_rect.height() as i32 - height as i32) / 2;
+
+        if let (true, Some((start, end))) = (self.has_focus(), self.get_selection()) {
+            let start_x = text_width(&text[..start]);
+            let end_x = text_width(&text[..end]);
+            renderer.set_draw_color(color.selection);
+            try!(renderer.fill_rect(Rect::new(x + start_x as i32, y, end_x - start_x, height)));
+        }
+
+        let (s, text_color) = if text.is_empty() {
+            (self.placeholder.borrow().clone(), color.insensitive_text)
+        }else if rendering_insensitive() {
+            (text.clone(), color.insensitive_text)
+        }else{
+            (text.clone(), color.text)
+        };
+        if !s.is_empty() {
+            let surface = try!(font.render(&s).blended(text_color).map_err(|e| e.to_string()));
+            let texture = try!(renderer.create_texture_from_surface(surface).map_err(|e| e.to_string()));
+            let (w, h) = try!(font.size_of(&s).map_err(|e| e.to_string()));
+            try!(renderer.copy(&texture, None, Some(Rect::new(x, y, w, h))));
+        }
+
+        if self.has_focus() {
+            let caret_x = x + text_width(&text[..self.caret.get()]) as i32;
+            renderer.set_draw_color(color.text);
+            try!(renderer.draw_line(Point::new(caret_x, y), Point::new(caret_x, y + height as i32 - 1)));
+        }
+        Ok(())
+    }
+}
+
+impl TextTrait for Entry {
+    /// Set the text and put the caret at the end
+    fn set_text(&self, new_text: &str) {
+        let new_text = &new_text[..self.fit_length(new_text, 0)];
+        let len = self.text.borrow().len();
+        if *self.text.borrow() == new_text {
+            self.set_caret(len);
+            return;
+        }
+        self.replace(0, len, new_text);
+    }
+
+    fn get_text(&self) -> String {
+        self.text.borrow().clone()
+    }
+}
+
+impl WidgetTrait for Entry {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
+    fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
+        let rect = self.rect.get().expect("No rectangle allocation at rendering");
+        let color = theme::get_theme_color();
+        let text_rect = self.text_rect(rect);
+        // Keep the caret inside the clip at the right end
+        self.scroll_to_caret(text_rect.width().saturating_sub(1));
+
+        renderer.set_draw_color(color.light_background);
+        try!(renderer.fill_rect(rect));
+        renderer.set_draw_color(color.dark_border);
+        try!(renderer.draw_rect(rect));
+
+        // Clip inside the clip rect of the parent
+        let before_clip = renderer.clip_rect();
+        let clip = match before_clip {
+            Some(c) => c.intersection(text_rect),
+            None => Some(text_rect),
+        };
+        let clip = match clip {
+            Some(c) => c,
+            None => { return Ok(()); },
+        };
+        renderer.set_clip_rect(Some(clip));
+        let result = self.render_text(renderer, text_rect);
+        renderer.set_clip_rect(before_clip);
+        result
+    }
+
+    fn alloc_rect(&self, rect: Rect) {
+        self.rect.set(Some(rect));
+    }
+
+    fn event_handler(&self, event: &Event) {
+        let rect = match self.rect.get() {
+            Some(r) => r,
+            None => { return; },
+        };
+
+        match *event {
+            Event::ButtonDown((x, y), Mouse::Left) if rect.contains((x, y)) => {
+                // Dragging from here selects text
+                self.grab_focus();
+                self.dragging.set(true);
+                let index = self.index_at(x);
+                self.move_caret(index, false);
+            },
+            Event::ButtonDown(_, _) => {
+                // The frame has taken the focus
+                self.anchor.set(None);
+            },
+            Event::MouseMotion((x, _)) if self.dragging.get() => {
+                let index = self.index_at(x);
+                self.move_caret(index, true);
+            },
+            Event::ButtonUp(_, Mouse::Left) => {
+                self.dragging.set(false);
+            },
+            Event::WindowFocusLost => {
+                self.cancel_input();
+            },
+            Event::TextInput(ref s) if self.has_focus() => {
+                self.insert(s);
+            },
+            Event::KeyDown(keycode, keymod) if self.has_focus() => {
+                self.key_down(keycode, keymod);
+            },
+            _ => (),
+        }
+    }
+
+    fn cancel_input(&self) {
+        self.dragging.set(false);
+    }
+
+    fn preferred_width(&self) -> SizeRange {
+        SizeRange::new(PADDING * 2 + 20, NATURAL_WIDTH)
+    }
+
+    fn preferred_height_for_width(&self, _width: u32) -> SizeRange {
+        let font_height = theme::get_default_font().height() as u32;
+        SizeRange::fixed(font_height + PADDING * 2)
+    }
+}
@@ -0,0 +1,211 @@
+
+use std::rc::Rc;
+use std::cell::{RefCell, Cell};
+use std::cmp::max;
+use ::sdl2::mouse::Mouse;
+use ::sdl2::keyboard::Keycode;
+
+use prelude::*;
+use theme;
+use widgets::aligned_rect;
+use uicontext::request_relayout;
+
+const ARROW_SIZE: u32 = 10;
+const SPACING: u32 = 4;
+
+/// Container whose child can be shown or hidden by clicking the header
+pub struct Expander {
+    common: WidgetCommon,
+    rect: Cell<Option<Rect>>,
+    label: RefCell<AnyWidget>,
+    child: RefCell<Option<AnyWidget>>,
+    expanded: Cell<bool>,
+    callback_expanded_changed: RefCell<Option<Rc<Fn(bool) + 'static>>>,
+}
+
+impl Expander {
+    pub fn new(label: &str) -> Rc<Expander> {
+        Expander::new_with_label_widget(::widgets::Label::new(label))
+    }
+
+    /// Create with a widget shown in the header next to the arrow
+    pub fn new_with_label_widget<W: Into<AnyWidget>>(w: W) -> Rc<Expander> {
+        Rc::new(Expander{
+            common: WidgetCommon::new(),
+            rect: Cell::new(None),
+            label: RefCell::new(w.into()),
+            child: RefCell::new(None),
+            expanded: Cell::new(false),
+            callback_expanded_changed: RefCell::new(None),
+        })
+    }
+
+    pub fn set_child<W: Into<AnyWidget>>(&self, w: W) {
+        *self.child.borrow_mut() = Some(w.into());
+        request_relayout();
+    }
+
+    pub fn set_label_widget<W: Into<AnyWidget>>(&self, w: W) {
+        *self.label.borrow_mut() = w.into();
+        request_relayout();
+    }
+
+    pub fn get_label_widget(&self) -> AnyWidget {
+        self.label.borrow().clone()
+    }
+
+    /// Show or hide the child
+    pub fn set_expanded(&self, expanded: bool) {
+        if self.expanded.get() == expanded {
+            return;
+        }
+        self.expanded.set(expanded);
+        // The size of this widget is changed, so the enclosing containers must be updated
+        request_relayout();
+
+        let callback = self.callback_expanded_changed.borrow().clone();
+        if let Some(f) = callback {
+            f(expanded);
+        }
+    }
+
+    pub fn get_expanded(&self) -> bool {
+        self.expanded.get()
+    }
+
+    /// Set a callback called when the child is shown or hidden
+    pub fn on_expanded_changed<F>(&self, f: F) where F: Fn(bool) + 'static {
+        *self.callback_expanded_changed.borrow_mut() = Some(Rc::new(f));
+    }
+
+    fn header_height(&self) -> u32 {
+        let label = self.label.borrow();
+        let height = label.preferred_height_for_width(label.preferred_width().natural).natural;
+        max(height, ARROW_SIZE)
+    }
+
+    fn header_rect(&self) -> Option<Rect> {
+        self.rect.get().map(|rect| Rect::new(rect.x(), rect.y(), rect.width(), self.header_height()))
+    }
+
+    fn render_arrow(&self, renderer: &mut Renderer, header: Rect) -> Result<(), String> {
+        let x = header.x();
+        let y = header.y() + (header.height() - ARROW_SIZE) as i32 / 2;
+        let size = ARROW_SIZE as i32;
+
+        renderer.set_draw_color(theme::get_text_color());
+        // Draw a filled triangle line by line
+        for i in 0..(size / 2) {
+            if self.expanded.get() {
+                // Pointing down
+                try!(renderer.draw_line(
+                    Point::new(x + i, y + size / 4 + i), Point::new(x + size - 1 - i, y + size / 4 + i)));
+            }else{
+                // Pointing right
+                try!(renderer.draw_line(
+                    Point::new(x + size / 4 + i, y + i), Point::new(x + size / 4 + i, y + size - 1 - i)));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl WidgetTrait for Expander {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
+    fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
+        let header = self.header_rect().expect("No rectangle allocation at rendering");
+        
+        try!(self.render_arrow(renderer, header));
+        try!(self.label.borrow().render(renderer));
+
+        if self.expanded.get() {
+            if let Some(ref c) = *self.child.borrow() {
+                try!(c.render(renderer));
+            }
+        }
+        Ok(())
+    }
+
+    fn alloc_rect(&self, rect: Rect) {
+        self.rect.set(Some(rect));
+        let header_height = self.header_height();
+
+        let label_x = (ARROW_SIZE + SPACING) as i32;
+        let label = self.label.borrow();
+        label.alloc_rect(aligned_rect(&*label, Rect::new(
+            rect.x() + label_x, rect.y(), rect.width().saturating_sub(label_x as u32), header_height)));
+
+        if let Some(ref c) = *self.child.borrow() {
+            let child_y = header_height + SPACING;
+            c.alloc_rect(aligned_rect(c, Rect::new(
+                rect.x(), rect.y() + child_y as i32, rect.width(), rect.height().saturating_sub(child_y))));
+        }
+    }
+
+    fn event_handler(&self, event: &Event) {
+        match *event {
+            Event::ButtonDown((x, y), mouse_btn) => {
+                let on_header = self.header_rect().map_or(false, |r| r.contains((x, y)));
+                if on_header {
+                    self.grab_focus();
+                }
+                if on_header && mouse_btn == Mouse::Left {
+                    self.set_expanded(!self.expanded.get());
+                }
+            },
+            Event::KeyDown(Keycode::Space, _) | Event::KeyDown(Keycode::Return, _)
+                if self.has_focus() => {
+                self.set_expanded(!self.expanded.get());
+            },
+            _ => (),
+        }
+
+        let label = self.label.borrow().clone();
+        label.event_handler(event);
+        
+        if self.expanded.get() {
+            let child = self.child.borrow().clone();
+            if let Some(c) = child {
+                c.event_handler(event);
+            }
+        }
+    }
+
+    fn cancel_input(&self) {
+        for c in self.children() {
+            c.cancel_input();
+        }
+    }
+
+    fn preferred_width(&self) -> SizeRange {
+        let header = self.label.borrow().preferred_width().add(ARROW_SIZE + SPACING);
+        match *self.child.borrow() {
+            Some(ref c) if self.expanded.get() => header.max(c.preferred_width()),
+            _ => header,
+        }
+    }
+
+    fn preferred_height_for_width(&self, width: u32) -> SizeRange {
+        let header = SizeRange::fixed(self.header_height());
+        match *self.child.borrow() {
+            Some(ref c) if self.expanded.get() => {
+                header.sum(c.preferred_height_for_width(width)).add(SPACING)
+            },
+            _ => header,
+        }
+    }
+}
+
+impl ContainerTrait for Expander {
+    fn with_children<F: FnMut(&AnyWidget)>(&self, f: F) {
+        let mut f = f;
+
+        f(&*self.label.borrow());
+        if let Some(ref c) = *self.child.borrow() {
+            f(c);
+        }
+    }
+}
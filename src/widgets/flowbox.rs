@@ -0,0 +1,263 @@
+
+use std::rc::Rc;
+use std::cell::{RefCell, Cell};
+use std::cmp::{min, max};
+
+use prelude::*;
+use enums::Align;
+use widgets::aligned_rect;
+use uicontext::request_relayout;
+
+/// Children in one line of a FlowBox
+struct Row {
+    items: Vec<(usize, u32)>, // Index and width of children
+    width: u32,
+    height: u32,
+}
+
+/// Container which arranges children from left to right and wraps them to new lines
+pub struct FlowBox {
+    common: WidgetCommon,
+    rect: Cell<Option<Rect>>,
+    children: RefCell<Vec<AnyWidget>>,
+    row_spacing: Cell<u32>,
+    column_spacing: Cell<u32>,
+    line_align: Cell<Align>,
+    box_updated: Cell<bool>,  // Positions of children is updated or not
+}
+
+impl FlowBox {
+    pub fn new() -> Rc<FlowBox> {
+        Rc::new(FlowBox{
+            common: WidgetCommon::new(),
+            rect: Cell::new(None),
+            children: RefCell::new(Vec::new()),
+            row_spacing: Cell::new(0),
+            column_spacing: Cell::new(0),
+            line_align: Cell::new(Align::Start),
+            box_updated: Cell::new(false),
+        })
+    }
+
+    pub fn add<W: Into<AnyWidget>>(&self, w: W) {
+        self.children.borrow_mut().push(w.into());
+        self.children_changed();
+    }
+
+    pub fn insert_at<W: Into<AnyWidget>>(&self, index: usize, w: W) {
+        {
+            let mut children = self.children.borrow_mut();
+            let index = min(index, children.len());
+            children.insert(index, w.into());
+        }
+        self.children_changed();
+    }
+
+    /// Remove the child. Returns false if the widget is not a child of this box.
+    pub fn remove<W: Into<AnyWidget>>(&self, w: W) -> bool {
+        let w = w.into();
+        let removed = {
+            let mut children = self.children.borrow_mut();
+            match children.iter().position(|c| *c == w) {
+                Some(i) => { children.remove(i); true },
+                None => false,
+            }
+        };
+        if removed {
+            self.children_changed();
+        }
+        removed
+    }
+
+    /// Move the child to the given position
+    pub fn reorder<W: Into<AnyWidget>>(&self, w: W, index: usize) {
+        let w = w.into();
+        {
+            let mut children = self.children.borrow_mut();
+            match children.iter().position(|c| *c == w) {
+                Some(i) => {
+                    let c = children.remove(i);
+                    let index = min(index, children.len());
+                    children.insert(index, c);
+                },
+                None => { return; },
+            }
+        }
+        self.children_changed();
+    }
+
+    /// Remove all children
+    pub fn clear(&self) {
+        self.children.borrow_mut().clear();
+        self.children_changed();
+    }
+
+    /// Set the space between lines
+    pub fn set_row_spacing(&self, spacing: u32) {
+        self.row_spacing.set(spacing);
+        self.children_changed();
+    }
+
+    pub fn get_row_spacing(&self) -> u32 {
+        self.row_spacing.get()
+    }
+
+    /// Set the space between children in a line
+    pub fn set_column_spacing(&self, spacing: u32) {
+        self.column_spacing.set(spacing);
+        self.children_changed();
+    }
+
+    pub fn get_column_spacing(&self) -> u32 {
+        self.column_spacing.get()
+    }
+
+    /// Set how children in a line are placed if the line is shorter than the box.
+    /// If Fill, the remaining space is shared by children.
+    pub fn set_line_align(&self, align: Align) {
+        self.line_align.set(align);
+        self.children_changed();
+    }
+
+    pub fn get_line_align(&self) -> Align {
+        self.line_align.get()
+    }
+
+    fn children_changed(&self) {
+        self.box_updated.set(false);
+        request_relayout();
+    }
+
+    /// Split children into lines which fit in the width
+    fn calc_rows(&self, width: u32) -> Vec<Row> {
+        let spacing = self.column_spacing.get();
+        let mut rows: Vec<Row> = Vec::new();
+
+        for (i, c) in self.children.borrow().iter().enumerate() {
+            let child_width = min(c.preferred_width().natural, width);
+            let child_height = c.preferred_height_for_width(child_width).natural;
+
+            let new_row = match rows.last() {
+                Some(row) => row.width + spacing + child_width > width,
+                None => true,
+            };
+            if new_row {
+                rows.push(Row{ items: vec![(i, child_width)], width: child_width, height: child_height });
+            }else{
+                let row = rows.last_mut().unwrap();
+                row.items.push((i, child_width));
+                row.width += spacing + child_width;
+                row.height = max(row.height, child_height);
+            }
+        }
+        rows
+    }
+
+    fn position_update(&self) {
+        let rect = match self.rect.get() {
+            Some(rect) => rect,
+            None => { return; },
+        };
+        let rows = self.calc_rows(rect.width());
+        let spacing = self.column_spacing.get();
+        let children = self.children.borrow();
+        let mut y = rect.y();
+
+        for row in rows.iter() {
+            let space = rect.width().saturating_sub(row.width);
+            let n = row.items.len() as u32;
+            let (mut x, extra) = match self.line_align.get() {
+                Align::Start => (rect.x(), 0),
+                Align::Center => (rect.x() + (space / 2) as i32, 0),
+                Align::End => (rect.x() + space as i32, 0),
+                Align::Fill => (rect.x(), space / n),
+            };
+
+            for &(i, width) in row.items.iter() {
+                let c = &children[i];
+                let width = width + extra;
+                c.alloc_rect(aligned_rect(c, Rect::new(x, y, width, row.height)));
+                x += (width + spacing) as i32;
+            }
+            y += (row.height + self.row_spacing.get()) as i32;
+        }
+
+        self.box_updated.set(true);
+    }
+}
+
+impl WidgetTrait for FlowBox {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
+    fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
+        if !self.box_updated.get() {
+            self.position_update();
+        }
+
+        let before_clip = renderer.clip_rect();
+        renderer.set_clip_rect(self.rect.get());
+
+        for c in self.children.borrow().iter() {
+            try!(c.render(renderer));
+        }
+
+        renderer.set_clip_rect(before_clip);
+        
+        Ok(())
+    }
+
+    fn alloc_rect(&self, rect: Rect) {
+        self.rect.set(Some(rect));
+        self.box_updated.set(false);
+    }
+
+    fn event_handler(&self, event: &Event) {
+        for w in self.children() {
+            w.event_handler(event);
+        }
+    }
+
+    fn cancel_input(&self) {
+        for c in self.children() {
+            c.cancel_input();
+        }
+    }
+
+    fn preferred_width(&self) -> SizeRange {
+        // Minimum is the widest child, and natural is all children in a line
+        let mut minimum = 0;
+        let mut natural = 0;
+        let children = self.children.borrow();
+        
+        for c in children.iter() {
+            let size = c.preferred_width();
+            minimum = max(minimum, size.minimum);
+            natural += size.natural;
+        }
+        if children.len() > 1 {
+            natural += self.column_spacing.get() * (children.len() as u32 - 1);
+        }
+        SizeRange::new(minimum, natural)
+    }
+
+    fn preferred_height_for_width(&self, width: u32) -> SizeRange {
+        let rows = self.calc_rows(width);
+        let mut height: u32 = rows.iter().map(|row| row.height).sum();
+        if rows.len() > 1 {
+            height += self.row_spacing.get() * (rows.len() as u32 - 1);
+        }
+        SizeRange::fixed(height)
+    }
+}
+
+impl ContainerTrait for FlowBox {
+    fn with_children<F: FnMut(&AnyWidget)>(&self, f: F) {
+        let mut f = f;
+
+        for c in self.children.borrow().iter() {
+            f(c);
+        }
+    }
+}
@@ -1,16 +1,25 @@
 
-use std::cell::{RefCell, Ref};
+use std::cell::{Cell, RefCell, Ref};
 use std::rc::Rc;
 
 use prelude::*;
 use uicontext;
+use widgets::aligned_rect;
 use ::sdl2::video::WindowRef;
+use ::sdl2::keyboard::Keycode;
+use event::{ctrl_pressed, shift_pressed};
+use widgets::AnyWidgetWeakRef;
+use widgets::debugoverlay::{render_debug_overlay, render_highlight};
 //use ::sdl2::video::WindowBuilder;
 
 /// Top level widget
 pub struct Frame {
+    common: WidgetCommon,
     renderer: RefCell<Renderer<'static>>,
     child: RefCell<Option<AnyWidget>>,
+    debug_overlay: Cell<bool>,
+    pointer: Cell<Option<(i32, i32)>>,
+    highlighted: RefCell<Option<AnyWidgetWeakRef>>,
 }
 
 impl Frame {
@@ -26,8 +35,12 @@ impl Frame {
         let renderer = window.renderer().build().unwrap();
 
         let frame = Rc::new(Frame{
+            common: WidgetCommon::new(),
             renderer: RefCell::new(renderer),
             child: RefCell::new(None),
+            debug_overlay: Cell::new(false),
+            pointer: Cell::new(None),
+            highlighted: RefCell::new(None),
         });
         uicontext::add_frame(frame.clone());
         frame
@@ -42,6 +55,13 @@ impl Frame {
 
         if let Some(ref mut child) = *self.child.borrow_mut() {
             let _ = child.render(&mut *renderer);
+            if self.debug_overlay.get() {
+                let _ = render_debug_overlay(&mut *renderer, child, self.pointer.get());
+            }
+            let highlighted = self.highlighted.borrow().as_ref().and_then(|w| w.upgrade());
+            if let Some(rect) = highlighted.and_then(|w| w.get_allocation()) {
+                let _ = render_highlight(&mut *renderer, rect);
+            }
         }
         
         renderer.present();
@@ -54,14 +74,101 @@ impl Frame {
         self.size_update();
     }
 
+    /// Remove the child. Returns false if the widget is not the child.
+    pub fn remove<W: Into<AnyWidget>>(&self, w: W) -> bool {
+        let w = w.into();
+        let is_child = self.child.borrow().as_ref() == Some(&w);
+        if is_child {
+            self.clear();
+        }
+        is_child
+    }
+
+    /// Remove the child if exists
+    pub fn clear(&self) {
+        *self.child.borrow_mut() = None;
+        uicontext::request_redraw();
+    }
+
+    /// Draw allocated rects, type names and requested sizes of all widgets.
+    /// Ctrl+Shift+D also toggles this.
+    pub fn set_debug_overlay(&self, enabled: bool) {
+        self.debug_overlay.set(enabled);
+        uicontext::request_redraw();
+    }
+
+    pub fn get_debug_overlay(&self) -> bool {
+        self.debug_overlay.get()
+    }
+
     // Update child widget's size
     pub fn size_update(&self) {
         if let Some(ref c) = *self.child.borrow() {
             let window_size = self.window().size();
-            c.alloc_rect(Rect::new(0, 0, window_size.0, window_size.1));
+            c.alloc_rect(aligned_rect(c, Rect::new(0, 0, window_size.0, window_size.1)));
         }
     }
 
+    /// Draw a mark over the widget. Used by the inspector.
+    pub fn set_highlighted_widget(&self, w: Option<&AnyWidget>) {
+        *self.highlighted.borrow_mut() = w.map(|w| w.weak_ref());
+        uicontext::request_redraw();
+    }
+
+    /// Give the focus to the widget which called grab_focus.
+    /// If several widgets called it, the innermost one gets the focus.
+    pub fn update_focus(&self) {
+        let mut requested = None;
+        let mut stack = self.children();
+        while let Some(w) = stack.pop() {
+            if w.common().focus_requested.get() {
+                w.common().focus_requested.set(false);
+                // Descendants are visited after the widget, so the innermost one is found last
+                requested = Some(w.clone());
+            }
+            stack.extend(w.children());
+        }
+        if let Some(w) = requested {
+            uicontext::set_focus(Some(&w));
+        }
+    }
+
+    // Widgets from the child of this frame to the focused widget.
+    // Empty if the focus is not in this frame.
+    fn focus_path(&self) -> Vec<AnyWidget> {
+        fn find(w: &AnyWidget, focus: &AnyWidget, path: &mut Vec<AnyWidget>) -> bool {
+            path.push(w.clone());
+            if w == focus || w.children().iter().any(|c| find(c, focus, path)) {
+                return true;
+            }
+            path.pop();
+            false
+        }
+
+        let mut path = Vec::new();
+        if let Some(focus) = uicontext::get_focus() {
+            for w in self.children() {
+                if find(&w, &focus, &mut path) {
+                    break;
+                }
+            }
+        }
+        path
+    }
+
+    /// Hide the window and stop drawing it. Other frames keep running.
+    pub fn close(&self) {
+        if let Some(window) = self.renderer.borrow_mut().window_mut() {
+            window.hide();
+        }
+        uicontext::remove_frame(self);
+    }
+
+    /// Id of the SDL window, used to send events to this frame
+    pub fn window_id(&self) -> u32 {
+        self.window().id()
+    }
+
     // Get window
     fn window(&self) -> Ref<WindowRef> {
         Ref::map(self.renderer.borrow(), |r| r.window().unwrap())
@@ -69,6 +176,10 @@ impl Frame {
 }
 
 impl WidgetTrait for Frame {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
     fn render(&self, _renderer: &mut Renderer) -> Result<(), String> {
         unimplemented!();
     }
@@ -78,9 +189,38 @@ impl WidgetTrait for Frame {
     }
 
     fn event_handler(&self, event: &Event) {
-        self.with_children(|w| {
+        match *event {
+            Event::KeyDown(Keycode::D, keymod) if ctrl_pressed(keymod) && shift_pressed(keymod) => {
+                self.set_debug_overlay(!self.debug_overlay.get());
+                return;
+            },
+            Event::MouseMotion(pointer) => {
+                self.pointer.set(Some(pointer));
+                if self.debug_overlay.get() {
+                    uicontext::request_redraw();
+                }
+            },
+            Event::ButtonDown(..) => {
+                // Widgets under the pointer grab the focus again
+                uicontext::set_focus(None);
+            },
+            Event::WindowFocusLost => {
+                self.pointer.set(None);
+                if !self.focus_path().is_empty() {
+                    uicontext::set_focus(None);
+                }
+            },
+            _ => (),
+        }
+
+        if event.is_key() {
+            // Key events are sent only to the focused widget and its ancestors
+            uicontext::set_focus_path(Some(self.focus_path()));
+        }
+        for w in self.children() {
             w.event_handler(event);
-        });
+        }
+        uicontext::set_focus_path(None);
     }
 }
 
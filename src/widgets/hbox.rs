@@ -2,29 +2,138 @@ use std::rc::Rc;
 use std::cell::{RefCell, Cell};
 
 use prelude::*;
+use uicontext::{request_redraw, request_relayout};
+use enums::{PackType, Direction};
+use widgets::aligned_rect;
+use super::boxlayout::{BoxChild, distribute, main_axis_size};
 
 pub struct HBox {
+    common: WidgetCommon,
     rect: Cell<Option<Rect>>,
-    max_height: Cell<u32>, // Max height of child widgets
-    children: RefCell<Vec<(AnyWidget, bool)>>,
+    children: RefCell<Vec<BoxChild>>,
+    spacing: Cell<u32>,
+    homogeneous: Cell<bool>,
     box_updated: Cell<bool>,  // Positions of children is updated or not
 }
 
 impl HBox {
     pub fn new() -> Rc<HBox> {
         Rc::new(HBox{
+            common: WidgetCommon::new(),
             rect: Cell::new(None),
-            max_height: Cell::new(0),
             children: RefCell::new(Vec::new()),
+            spacing: Cell::new(0),
+            homogeneous: Cell::new(false),
             box_updated: Cell::new(false),
         })
     }
 
     pub fn pack<W: Into<AnyWidget>>(&self, w: W, expand: bool) {
+        self.pack_start(w, expand, true, 0);
+    }
+
+    /// Pack a child from the left.
+    /// If fill is false, the child keeps its requested width in the expanded space.
+    pub fn pack_start<W: Into<AnyWidget>>(&self, w: W, expand: bool, fill: bool, padding: u32) {
+        self.add_child(w.into(), expand, fill, padding, PackType::Start);
+    }
+
+    /// Pack a child from the right
+    pub fn pack_end<W: Into<AnyWidget>>(&self, w: W, expand: bool, fill: bool, padding: u32) {
+        self.add_child(w.into(), expand, fill, padding, PackType::End);
+    }
+
+    /// Set the space between children
+    pub fn set_spacing(&self, spacing: u32) {
+        self.spacing.set(spacing);
+        self.box_updated.set(false);
+        request_redraw();
+    }
+
+    pub fn get_spacing(&self) -> u32 {
+        self.spacing.get()
+    }
+
+    /// If homogeneous is true, all children are allocated the same width
+    pub fn set_homogeneous(&self, homogeneous: bool) {
+        self.homogeneous.set(homogeneous);
+        self.box_updated.set(false);
+        request_redraw();
+    }
+
+    pub fn get_homogeneous(&self) -> bool {
+        self.homogeneous.get()
+    }
+
+    /// Insert a child at the given position in the packing order.
+    /// The other arguments are the same as pack_start and pack_end.
+    pub fn insert_at<W: Into<AnyWidget>>(&self, index: usize, w: W, expand: bool, fill: bool, padding: u32,
+                                         pack_type: PackType) {
+        {
+            let mut children = self.children.borrow_mut();
+            let index = ::std::cmp::min(index, children.len());
+            children.insert(index, BoxChild{
+                widget: w.into(), expand: expand, fill: fill, padding: padding, pack_type: pack_type,
+            });
+        }
+        self.children_changed();
+    }
+
+    /// Remove the child. Returns false if the widget is not a child of this box.
+    pub fn remove<W: Into<AnyWidget>>(&self, w: W) -> bool {
+        let w = w.into();
+        let removed = {
+            let mut children = self.children.borrow_mut();
+            if let Some(i) = children.iter().position(|c| c.widget == w) {
+                children.remove(i);
+                true
+            }else{
+                false
+            }
+        };
+
+        if removed {
+            self.children_changed();
+        }
+        removed
+    }
+
+    /// Move the child to the given position in the packing order
+    pub fn reorder<W: Into<AnyWidget>>(&self, w: W, index: usize) {
         let w = w.into();
+        {
+            let mut children = self.children.borrow_mut();
+            if let Some(i) = children.iter().position(|c| c.widget == w) {
+                let c = children.remove(i);
+                let index = ::std::cmp::min(index, children.len());
+                children.insert(index, c);
+            }else{
+                return;
+            }
+        }
+        self.children_changed();
+    }
+
+    /// Remove all children
+    pub fn clear(&self) {
+        self.children.borrow_mut().clear();
+        self.children_changed();
+    }
+
+    fn add_child(&self, w: AnyWidget, expand: bool, fill: bool, padding: u32, pack_type: PackType) {
+        let len = self.children.borrow().len();
+        self.insert_at(len, w, expand, fill, padding, pack_type);
+    }
 
-        self.children.borrow_mut().push((w, expand));
+    fn children_changed(&self) {
         self.box_updated.set(false);
+        request_relayout();
+    }
+
+    fn distribute_width(&self, children: &[BoxChild], box_width: u32) -> Vec<(i32, u32)> {
+        let widths: Vec<SizeRange> = children.iter().map(|c| c.widget.preferred_width()).collect();
+
+        distribute(children, &widths, box_width, self.spacing.get(), self.homogeneous.get(), Direction::Horizon)
     }
 
     fn position_update(&self) {
@@ -38,39 +147,22 @@ impl HBox {
         let box_x = box_rect.x();
         let box_y = box_rect.y();
 
-        let mut sum_requested_width = 0;
-        let mut max_height = 0;
-        let mut n_expand_widget = 0;
-        
-        for &(ref c, expand) in self.children.borrow().iter() {
-            if let Some((w, h)) = c.requested_size() {
-                sum_requested_width += w;
-                if h > max_height { max_height = h }
-            }
-            if expand { n_expand_widget += 1 }
-        }
-
-        let expand_widget_width = if box_width < sum_requested_width  || n_expand_widget == 0 {
-            0
-        }else{
-            (box_width - sum_requested_width) / n_expand_widget
-        };
+        let children = self.children.borrow();
+        let positions = self.distribute_width(&children, box_width);
 
-        let mut x = box_x;
-        for &(ref c, expand) in self.children.borrow().iter() {
-            let requested_width = if let Some((w, _)) = c.requested_size() { w }else{ 0 };
-            let mut width = requested_width;
-            if expand { width += expand_widget_width }
-            c.alloc_rect(Rect::from((x, box_y, width, box_height)));
-            x += width as i32;
+        for (c, &(x, width)) in children.iter().zip(positions.iter()) {
+            c.widget.alloc_rect(aligned_rect(&c.widget, Rect::from((box_x + x, box_y, width, box_height))));
         }
         
         self.box_updated.set(true);
-        self.max_height.set(max_height);
     }
 }
 
 impl WidgetTrait for HBox {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
     fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
         if !self.box_updated.get() {
             self.position_update();
@@ -79,8 +171,8 @@ impl WidgetTrait for HBox {
         let before_clip = renderer.clip_rect();
         renderer.set_clip_rect(self.rect.get());
 
-        for &(ref c, _) in self.children.borrow().iter() {
-            try!(c.render(renderer));
+        for c in self.children.borrow().iter() {
+            try!(c.widget.render(renderer));
         }
 
         renderer.set_clip_rect(before_clip);
@@ -94,21 +186,33 @@ impl WidgetTrait for HBox {
     }
 
     fn event_handler(&self, event: &Event) {
-        self.with_children(|w| {
+        for w in self.children() {
             w.event_handler(event);
-        });
+        }
     }
 
-    fn requested_size(&self) -> Option<(u32, u32)> {
-        let mut max_height = 0;
-        self.with_children(|widget| {
-            if let Some((_, h)) = widget.requested_size() {
-                if max_height < h { max_height = h; }
-            }
-        });
+    fn cancel_input(&self) {
+        for c in self.children() {
+            c.cancel_input();
+        }
+    }
+
+    fn preferred_width(&self) -> SizeRange {
+        let children = self.children.borrow();
+        let widths: Vec<SizeRange> = children.iter().map(|c| c.widget.preferred_width()).collect();
+
+        main_axis_size(&children, &widths, self.spacing.get(), self.homogeneous.get())
+    }
+
+    fn preferred_height_for_width(&self, width: u32) -> SizeRange {
+        let children = self.children.borrow();
+        let positions = self.distribute_width(&children, width);
 
-        self.max_height.set(max_height);
-        Some((1, max_height))
+        let mut size = SizeRange::default();
+        for (c, &(_, width)) in children.iter().zip(positions.iter()) {
+            size = size.max(c.widget.preferred_height_for_width(width));
+        }
+        size
     }
 }
 
@@ -116,8 +220,8 @@ impl ContainerTrait for HBox {
     fn with_children<F: FnMut(&AnyWidget)>(&self, f: F) {
         let mut f = f;
 
-        for &(ref c, _) in self.children.borrow().iter() {
-            f(&c);
+        for c in self.children.borrow().iter() {
+            f(&c.widget);
         }
     }
 }
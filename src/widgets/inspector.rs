@@ -0,0 +1,451 @@
+use std::rc::{Rc, Weak};
+use std::cell::{RefCell, Cell};
+use std::time::Duration;
+use ::sdl2::keyboard::Keycode;
+
+use prelude::*;
+use enums::Align;
+use uicontext::{request_relayout, add_timeout};
+use widgets::{AnyWidgetWeakRef, Frame, HPaned, VBox, HBox, MarginBox, ScrolledWindow, Button, Label};
+
+// Interval of checking the selected widget for changes
+const UPDATE_INTERVAL: u64 = 500;
+
+/// Shows the widget tree of another frame.
+/// The selected widget is highlighted in that frame and its properties are shown
+/// and kept up to date. Text and margins of the selected widget can be edited.
+pub struct Inspector {
+    common: WidgetCommon,
+    target: Weak<Frame>,
+    root: AnyWidget,
+    tree: Rc<VBox>,
+    details: Rc<VBox>,
+    selected: RefCell<Option<AnyWidgetWeakRef>>,
+    // While true, typed text goes to the selected widget. Ends when the inspector loses the focus.
+    editing: Cell<bool>,
+    text_label: RefCell<Option<Rc<Label>>>,
+    // Properties and text shown in the details, compared to find changes
+    shown_state: RefCell<Vec<String>>,
+    weak_self: RefCell<Weak<Inspector>>,
+}
+
+impl Inspector {
+    pub fn new(target: &Rc<Frame>) -> Rc<Inspector> {
+        let tree = VBox::new();
+        let details = VBox::new();
+        details.set_spacing(2);
+
+        let refresh_button = Button::new_with_label("Refresh");
+        let tree_window = ScrolledWindow::new();
+        tree_window.set_child(tree.clone());
+        let left = VBox::new();
+        left.pack(refresh_button.clone(), false);
+        left.pack(tree_window, true);
+
+        let details_window = ScrolledWindow::new();
+        details_window.set_child(MarginBox::new(details.clone(), 5, 5, 5, 5));
+
+        let paned = HPaned::new();
+        paned.pack1(left, false);
+        paned.pack2(details_window, true);
+        paned.set_position(200);
+
+        let inspector = Rc::new(Inspector{
+            common: WidgetCommon::new(),
+            target: Rc::downgrade(target),
+            root: paned.into(),
+            tree: tree,
+            details: details,
+            selected: RefCell::new(None),
+            editing: Cell::new(false),
+            text_label: RefCell::new(None),
+            shown_state: RefCell::new(Vec::new()),
+            weak_self: RefCell::new(Weak::new()),
+        });
+        *inspector.weak_self.borrow_mut() = Rc::downgrade(&inspector);
+
+        let weak = Rc::downgrade(&inspector);
+        refresh_button.on_clicked(move || {
+            if let Some(i) = weak.upgrade() { i.refresh(); }
+        });
+
+        inspector.refresh();
+        schedule_update(Rc::downgrade(&inspector));
+        inspector
+    }
+
+    /// Open a new window inspecting the frame
+    pub fn open(target: &Rc<Frame>) -> Rc<Inspector> {
+        let frame = Frame::new("Inspector", 600, 400, true);
+        let inspector = Inspector::new(target);
+        frame.set_child(inspector.clone());
+        inspector
+    }
+
+    /// Select a widget and show its properties
+    pub fn select(&self, w: &AnyWidget) {
+        *self.selected.borrow_mut() = Some(w.weak_ref());
+        self.editing.set(false);
+        if let Some(target) = self.target.upgrade() {
+            target.set_highlighted_widget(Some(w));
+        }
+        self.refresh();
+    }
+
+    pub fn get_selected(&self) -> Option<AnyWidget> {
+        self.selected.borrow().as_ref().and_then(|w| w.upgrade())
+    }
+
+    /// Rebuild the tree and the properties from the current state of the frame
+    pub fn refresh(&self) {
+        self.tree.clear();
+        if let Some(target) = self.target.upgrade() {
+            for c in target.children() {
+                self.add_tree_row(&c, 0);
+            }
+        }
+        self.update_details();
+    }
+
+    fn add_tree_row(&self, w: &AnyWidget, depth: u32) {
+        let marker = if self.get_selected().as_ref() == Some(w) { "> " }else{ "" };
+        let button = Button::new_with_label(&format!("{}{}", marker, w.type_name()));
+        button.set_halign(Align::Start);
+
+        let weak = self.weak_self.borrow().clone();
+        let widget = w.weak_ref();
+        button.on_clicked(move || {
+            if let (Some(i), Some(w)) = (weak.upgrade(), widget.upgrade()) {
+                i.select(&w);
+            }
+        });
+        self.tree.pack(MarginBox::new(button, 0, 0, depth * 16, 0), false);
+
+        for c in w.children() {
+            self.add_tree_row(&c, depth + 1);
+        }
+    }
+
+    fn update_details(&self) {
+        self.details.clear();
+        *self.text_label.borrow_mut() = None;
+
+        let w = match self.get_selected() {
+            Some(w) => w,
+            None => {
+                self.shown_state.borrow_mut().clear();
+                self.add_detail_line("Select a widget");
+                return;
+            },
+        };
+
+        *self.shown_state.borrow_mut() = current_state(&w);
+        for line in detail_lines(&w) {
+            self.add_detail_line(&line);
+        }
+
+        if let Some(t) = text_widget(&w) {
+            let label = Label::new("");
+            label.set_halign(Align::Start);
+            *self.text_label.borrow_mut() = Some(label.clone());
+            self.update_text_label(&t.get_text());
+
+            let button = Button::new_with_label(if self.editing.get() { "Done" }else{ "Edit" });
+            let weak = self.weak_self.borrow().clone();
+            button.on_clicked(move || {
+                if let Some(i) = weak.upgrade() {
+                    i.editing.set(!i.editing.get());
+                    if i.editing.get() {
+                        i.grab_focus();
+                    }
+                    i.update_details();
+                }
+            });
+
+            let row = HBox::new();
+            row.set_spacing(5);
+            row.pack(label, false);
+            row.pack(button, false);
+            self.details.pack(row, false);
+        }
+
+        if let AnyWidget::MarginBox(ref b) = w {
+            let names = ["top", "bottom", "left", "right"];
+            for (side, name) in names.iter().enumerate() {
+                self.add_margin_row(b, side, name);
+            }
+        }
+    }
+
+    fn add_detail_line(&self, s: &str) {
+        let label = Label::new(s);
+        label.set_halign(Align::Start);
+        self.details.pack(label, false);
+    }
+
+    /// A row with buttons changing one of the margins
+    fn add_margin_row(&self, b: &Rc<MarginBox>, side: usize, name: &str) {
+        let get = |b: &MarginBox| {
+            let (top, bottom, left, right) = b.get_margins();
+            [top, bottom, left, right]
+        };
+
+        let row = HBox::new();
+        row.set_spacing(5);
+        row.pack(Label::new(&format!("{}: {}", name, get(b)[side])), false);
+
+        for &delta in &[-1i32, 1] {
+            let button = Button::new_with_label(if delta < 0 { "-" }else{ "+" });
+            let weak = self.weak_self.borrow().clone();
+            let b = Rc::downgrade(b);
+            button.on_clicked(move || {
+                if let (Some(i), Some(b)) = (weak.upgrade(), b.upgrade()) {
+                    let mut m = get(&b);
+                    m[side] = ::std::cmp::max(m[side] as i32 + delta, 0) as u32;
+                    b.set_margins(m[0], m[1], m[2], m[3]);
+                    i.update_details();
+                }
+            });
+            row.pack(button, false);
+        }
+        self.details.pack(row, false);
+    }
+
+    fn update_text_label(&self, text: &str) {
+        if let Some(ref label) = *self.text_label.borrow() {
+            let caret = if self.editing.get() { "|" }else{ "" };
+            label.set_text(&format!("Text: {}{}", text, caret));
+        }
+    }
+
+    // Show the changes of the selected widget made by the application or the user
+    fn update_if_changed(&self) {
+        let changed = match self.get_selected() {
+            Some(w) => current_state(&w) != *self.shown_state.borrow(),
+            None => false,
+        };
+        if changed {
+            self.update_details();
+        }
+    }
+
+    // Stop editing if the focus has moved to another widget.
+    // The focus requested by the Edit button is given after the event.
+    fn check_focus(&self) {
+        if self.editing.get() && !self.has_focus() && !self.common.focus_requested.get() {
+            self.editing.set(false);
+            self.update_details();
+        }
+    }
+
+    /// Change the text of the selected widget while editing
+    fn edit_text<F: FnOnce(&mut String)>(&self, f: F) {
+        if let Some(t) = self.get_selected().as_ref().and_then(text_widget) {
+            let mut text = t.get_text();
+            f(&mut text);
+            t.set_text(&text);
+            self.update_text_label(&text);
+            if let Some(w) = self.get_selected() {
+                *self.shown_state.borrow_mut() = current_state(&w);
+            }
+            request_relayout();
+        }
+    }
+}
+
+fn schedule_update(weak: Weak<Inspector>) {
+    add_timeout(Duration::from_millis(UPDATE_INTERVAL), move || {
+        if let Some(inspector) = weak.upgrade() {
+            inspector.check_focus();
+            inspector.update_if_changed();
+            schedule_update(Rc::downgrade(&inspector));
+        }
+    });
+}
+
+/// Type, rect, size and properties shown in the details
+fn detail_lines(w: &AnyWidget) -> Vec<String> {
+    let mut lines = vec![format!("Type: {}", w.type_name())];
+    match w.get_allocation() {
+        Some(r) => lines.push(format!("Rect: ({}, {}) {}x{}", r.x(), r.y(), r.width(), r.height())),
+        None => lines.push("Rect: not allocated".to_owned()),
+    }
+    let (width, height) = w.requested_size();
+    let min_width = w.preferred_width().minimum;
+    let min_height = w.preferred_height_for_width(width).minimum;
+    lines.push(format!("Requested: {}x{} (minimum {}x{})", width, height, min_width, min_height));
+    lines.extend(state_lines(w));
+    lines
+}
+
+/// Everything shown in the details, including the text and the margins
+fn current_state(w: &AnyWidget) -> Vec<String> {
+    let mut state = detail_lines(w);
+    if let Some(t) = text_widget(w) {
+        state.push(t.get_text());
+    }
+    if let AnyWidget::MarginBox(ref b) = *w {
+        state.push(format!("{:?}", b.get_margins()));
+    }
+    state
+}
+
+/// Widgets which have TextTrait
+fn text_widget(w: &AnyWidget) -> Option<Rc<TextTrait>> {
+    match *w {
+        AnyWidget::Label(ref l) => Some(l.clone()),
+        AnyWidget::Entry(ref e) => Some(e.clone()),
+        AnyWidget::TextView(ref t) => Some(t.clone()),
+        _ => None,
+    }
+}
+
+/// Properties shown in the inspector besides type, rect and size
+fn state_lines(w: &AnyWidget) -> Vec<String> {
+    let mut lines = vec![
+        format!("halign: {:?}, valign: {:?}", w.get_halign(), w.get_valign()),
+        format!("hexpand: {}, vexpand: {}", w.get_hexpand(), w.get_vexpand()),
+        format!("visible: {}, sensitive: {}", w.get_visible(), w.get_sensitive()),
+    ];
+
+    let show = |size: Option<u32>| size.map(|s| s.to_string()).unwrap_or("-".to_owned());
+    let (min_width, min_height) = w.get_min_size();
+    let (max_width, max_height) = w.get_max_size();
+    if min_width.is_some() || min_height.is_some() {
+        lines.push(format!("min size: {}x{}", show(min_width), show(min_height)));
+    }
+    if max_width.is_some() || max_height.is_some() {
+        lines.push(format!("max size: {}x{}", show(max_width), show(max_height)));
+    }
+    if let Some(ratio) = w.get_aspect_ratio() {
+        lines.push(format!("aspect ratio: {}", ratio));
+    }
+
+    match *w {
+        AnyWidget::VBox(ref b) => {
+            lines.push(format!("spacing: {}, homogeneous: {}", b.get_spacing(), b.get_homogeneous()));
+        },
+        AnyWidget::HBox(ref b) => {
+            lines.push(format!("spacing: {}, homogeneous: {}", b.get_spacing(), b.get_homogeneous()));
+        },
+        AnyWidget::Notebook(ref n) => {
+            lines.push(format!("page: {} of {}", n.get_current_page() + 1, n.n_pages()));
+        },
+        AnyWidget::Stack(ref s) => {
+            lines.push(format!("visible child: {}", s.get_visible_child_name().unwrap_or_default()));
+        },
+        AnyWidget::HPaned(ref p) => {
+            lines.push(format!("position: {}", p.get_position()));
+        },
+        AnyWidget::VPaned(ref p) => {
+            lines.push(format!("position: {}", p.get_position()));
+        },
+        AnyWidget::ScrolledWindow(ref s) => {
+            let (x, y) = s.get_scroll_offset();
+            lines.push(format!("scroll offset: ({}, {})", x, y));
+        },
+        AnyWidget::Expander(ref e) => {
+            lines.push(format!("expanded: {}", e.get_expanded()));
+        },
+        AnyWidget::HScrollBar(ref s) => {
+            lines.push(format!("value: {} in [{}, {}]", s.get_value(), s.get_lower(), s.get_upper()));
+        },
+        AnyWidget::VScrollBar(ref s) => {
+            lines.push(format!("value: {} in [{}, {}]", s.get_value(), s.get_lower(), s.get_upper()));
+        },
+        AnyWidget::HScale(ref s) => {
+            lines.push(format!("value: {} in [{}, {}], step: {}",
+                               s.get_value(), s.get_lower(), s.get_upper(), s.get_step()));
+        },
+        AnyWidget::VScale(ref s) => {
+            lines.push(format!("value: {} in [{}, {}], step: {}",
+                               s.get_value(), s.get_lower(), s.get_upper(), s.get_step()));
+        },
+        AnyWidget::CheckButton(ref c) => {
+            lines.push(format!("state: {:?}", c.get_state()));
+        },
+        AnyWidget::RadioButton(ref r) => {
+            lines.push(format!("index: {}, value: {}, active: {}", r.get_index(), r.get_value(), r.get_active()));
+        },
+        AnyWidget::Label(ref l) => {
+            lines.push(format!("wrap: {}", l.get_wrap()));
+        },
+        AnyWidget::Entry(ref e) => {
+            lines.push(format!("caret: {}, max length: {:?}", e.get_caret(), e.get_max_length()));
+        },
+        AnyWidget::TextView(ref t) => {
+            let caret = t.get_caret();
+            lines.push(format!("lines: {}, caret: {}:{}", t.get_buffer().line_count(), caret.line, caret.index));
+            lines.push(format!("wrap: {}, editable: {}", t.get_wrap(), t.get_editable()));
+        },
+        _ => (),
+    }
+    lines
+}
+
+impl Drop for Inspector {
+    fn drop(&mut self) {
+        if let Some(target) = self.target.upgrade() {
+            target.set_highlighted_widget(None);
+        }
+    }
+}
+
+impl WidgetTrait for Inspector {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
+    fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
+        self.root.render(renderer)
+    }
+
+    fn alloc_rect(&self, rect: Rect) {
+        self.root.alloc_rect(rect);
+    }
+
+    fn event_handler(&self, event: &Event) {
+        if self.editing.get() && self.has_focus() {
+            match *event {
+                Event::TextInput(ref s) => {
+                    self.edit_text(|text| text.push_str(s));
+                    return;
+                },
+                Event::KeyDown(Keycode::Backspace, _) => {
+                    self.edit_text(|text| { text.pop(); });
+                    return;
+                },
+                Event::KeyDown(Keycode::Return, _) => {
+                    self.editing.set(false);
+                    self.update_details();
+                    return;
+                },
+                _ => (),
+            }
+        }
+
+        self.root.event_handler(event);
+        self.check_focus();
+    }
+
+    fn cancel_input(&self) {
+        for c in self.children() {
+            c.cancel_input();
+        }
+    }
+
+    fn preferred_width(&self) -> SizeRange {
+        self.root.preferred_width()
+    }
+
+    fn preferred_height_for_width(&self, width: u32) -> SizeRange {
+        self.root.preferred_height_for_width(width)
+    }
+}
+
+impl ContainerTrait for Inspector {
+    fn with_children<F: FnMut(&AnyWidget)>(&self, f: F) {
+        let mut f = f;
+        f(&self.root);
+    }
+}
@@ -4,47 +4,141 @@ use std::cell::{RefCell, Cell};
 use sdl2::surface::Surface;
 
 use prelude::*;
-use uicontext::request_redraw;
+use uicontext::{request_redraw, request_relayout};
+use widgets::rendering_insensitive;
+
+// Number of widths whose wrapped lines are kept
+const WRAP_CACHE_SIZE: usize = 4;
 
 pub struct Label {
+    common: WidgetCommon,
     label_str: RefCell<String>,
     rect: Cell<Option<Rect>>,
     text_size: Cell<(u32, u32)>,
+    longest_word: Cell<u32>,
+    wrap: Cell<bool>,
+    // Wrapped lines for recently used widths. Cleared when the text is changed.
+    wrap_cache: RefCell<Vec<(u32, Rc<Vec<String>>)>>,
 }
 
 impl Label {
     pub fn new(s: &str) -> Rc<Label> {
         let label = Label{
+            common: WidgetCommon::new(),
             label_str: RefCell::new(s.to_string()),
             rect: Cell::new(None),
             text_size: Cell::new((0, 0)),
+            longest_word: Cell::new(0),
+            wrap: Cell::new(false),
+            wrap_cache: RefCell::new(Vec::new()),
         };
         label.recalc_textsize();
         Rc::new(label)
     }
 
+    /// If wrap is true, the text is broken into lines at spaces to fit the allocated width
+    pub fn set_wrap(&self, wrap: bool) {
+        self.wrap.set(wrap);
+        request_relayout();
+    }
+
+    pub fn get_wrap(&self) -> bool {
+        self.wrap.get()
+    }
+
     fn recalc_textsize(&self) {
         let font = ::theme::get_default_font();
         let text_size = font.size_of(&*self.label_str.borrow()).expect("Text size calculation");
         self.text_size.set(text_size);
+        self.longest_word.set(self.longest_word_width());
+        self.wrap_cache.borrow_mut().clear();
+    }
+
+    /// Wrapped lines for the width, measured only once for each text and width
+    fn wrapped_lines(&self, width: u32) -> Rc<Vec<String>> {
+        if let Some(&(_, ref lines)) = self.wrap_cache.borrow().iter().find(|c| c.0 == width) {
+            return lines.clone();
+        }
+        let lines = Rc::new(self.wrap_lines(width));
+        let mut cache = self.wrap_cache.borrow_mut();
+        if cache.len() >= WRAP_CACHE_SIZE {
+            cache.remove(0);
+        }
+        cache.push((width, lines.clone()));
+        lines
+    }
+
+    /// Split the text into lines which are narrower than the given width if possible
+    fn wrap_lines(&self, width: u32) -> Vec<String> {
+        let font = ::theme::get_default_font();
+        let text_width = |s: &str| font.size_of(s).map(|size| size.0).unwrap_or(0);
+        let mut lines = Vec::new();
+
+        for paragraph in self.label_str.borrow().split('\n') {
+            let mut line = String::new();
+            for word in paragraph.split_whitespace() {
+                if line.is_empty() {
+                    line.push_str(word);
+                    continue;
+                }
+                let candidate = format!("{} {}", line, word);
+                if text_width(&candidate) > width {
+                    lines.push(line);
+                    line = word.to_owned();
+                }else{
+                    line = candidate;
+                }
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// Width of the longest word. Wrapped text cannot be narrower than this.
+    fn longest_word_width(&self) -> u32 {
+        let font = ::theme::get_default_font();
+        self.label_str.borrow().split_whitespace()
+            .map(|word| font.size_of(word).map(|size| size.0).unwrap_or(0))
+            .max().unwrap_or(0)
     }
 }
 
 impl WidgetTrait for Label {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
     fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
         let font = ::theme::get_default_font();
-        let surface: Surface = font.render(&*self.label_str.borrow()).blended(
-            ::theme::get_text_color()).expect("b");
-        let texture = renderer.create_texture_from_surface(surface).expect("a");
-
         let (x, y, w, h) = self.rect.get().expect("Rect").into();
-        let (text_w, text_h) = self.text_size.get();
+
+        let lines = if self.wrap.get() {
+            self.wrapped_lines(w)
+        }else{
+            Rc::new(vec![self.label_str.borrow().clone()])
+        };
+        let text_color = if rendering_insensitive() {
+            ::theme::get_theme_color().insensitive_text
+        }else{
+            ::theme::get_text_color()
+        };
+        let line_height = self.text_size.get().1;
+        let total_height = line_height * lines.len() as u32;
 
         // Centering text if given rect is bigger than text size
-        let x = if w > text_w { x + (w - text_w) as i32 / 2 }else{ x };
-        let y = if h > text_h { y + (h - text_h) as i32 / 2 }else{ y };
-        
-        renderer.copy(&texture, None, Some(Rect::new(x, y, text_w, text_h)));
+        let mut y = if h > total_height { y + (h - total_height) as i32 / 2 }else{ y };
+
+        for line in lines.iter() {
+            if !line.is_empty() {
+                let surface: Surface = font.render(line).blended(text_color).expect("b");
+                let texture = renderer.create_texture_from_surface(surface).expect("a");
+                let (text_w, text_h) = font.size_of(line).expect("Text size calculation");
+
+                let x = if w > text_w { x + (w - text_w) as i32 / 2 }else{ x };
+                renderer.copy(&texture, None, Some(Rect::new(x, y, text_w, text_h)));
+            }
+            y += line_height as i32;
+        }
 
         Ok(())
     }
@@ -53,8 +147,22 @@ impl WidgetTrait for Label {
         self.rect.set(Some(rect));
     }
 
-    fn requested_size(&self) -> Option<(u32, u32)> {
-        Some(self.text_size.get())
+    fn preferred_width(&self) -> SizeRange {
+        let text_width = self.text_size.get().0;
+        if self.wrap.get() {
+            SizeRange::new(self.longest_word.get(), text_width)
+        }else{
+            SizeRange::fixed(text_width)
+        }
+    }
+
+    fn preferred_height_for_width(&self, width: u32) -> SizeRange {
+        let line_height = self.text_size.get().1;
+        if self.wrap.get() {
+            SizeRange::fixed(line_height * self.wrapped_lines(width).len() as u32)
+        }else{
+            SizeRange::fixed(line_height)
+        }
     }
 }
 
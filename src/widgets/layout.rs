@@ -1,30 +1,285 @@
 use std::rc::Rc;
 use std::cell::{Cell, RefCell};
+use std::cmp::max;
 
 use prelude::*;
 use ::theme::get_background_color;
+use uicontext::request_redraw;
 
+/// A length in Layout. Percentages are relative to the size of the Layout.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Length {
+    Px(i32),
+    Percent(f64),
+}
+
+impl Length {
+    fn resolve(self, total: u32) -> i32 {
+        match self {
+            Length::Px(px) => px,
+            Length::Percent(p) => (total as f64 * p / 100.0) as i32,
+        }
+    }
+
+    fn px_or_zero(self) -> u32 {
+        match self {
+            Length::Px(px) if px > 0 => px as u32,
+            _ => 0,
+        }
+    }
+}
+
+/// Placement of a child of Layout along one axis
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AxisAnchor {
+    /// Offset from the left or top edge
+    Start(Length),
+    /// Offset from the right or bottom edge
+    End(Length),
+    /// Offsets from both edges. The child is stretched with the Layout.
+    Both(Length, Length),
+    /// Centered and shifted by the offset
+    Center(Length),
+}
+
+/// Position and size of a child of Layout.
+/// They are recalculated whenever the Layout is resized.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Anchor {
+    pub x: AxisAnchor,
+    pub y: AxisAnchor,
+    /// If None, the natural size of the child is used. Ignored for AxisAnchor::Both.
+    pub width: Option<Length>,
+    pub height: Option<Length>,
+}
+
+impl Anchor {
+    /// Placed at the position from the top left corner with the natural size
+    pub fn new(x: AxisAnchor, y: AxisAnchor) -> Anchor {
+        Anchor{ x: x, y: y, width: None, height: None }
+    }
+
+    /// Fixed position and size from the top left corner
+    pub fn fixed(rect: Rect) -> Anchor {
+        Anchor{
+            x: AxisAnchor::Start(Length::Px(rect.x())),
+            y: AxisAnchor::Start(Length::Px(rect.y())),
+            width: Some(Length::Px(rect.width() as i32)),
+            height: Some(Length::Px(rect.height() as i32)),
+        }
+    }
+
+    pub fn top_left(left: i32, top: i32) -> Anchor {
+        Anchor::new(AxisAnchor::Start(Length::Px(left)), AxisAnchor::Start(Length::Px(top)))
+    }
+
+    pub fn top_right(right: i32, top: i32) -> Anchor {
+        Anchor::new(AxisAnchor::End(Length::Px(right)), AxisAnchor::Start(Length::Px(top)))
+    }
+
+    pub fn bottom_left(left: i32, bottom: i32) -> Anchor {
+        Anchor::new(AxisAnchor::Start(Length::Px(left)), AxisAnchor::End(Length::Px(bottom)))
+    }
+
+    pub fn bottom_right(right: i32, bottom: i32) -> Anchor {
+        Anchor::new(AxisAnchor::End(Length::Px(right)), AxisAnchor::End(Length::Px(bottom)))
+    }
+
+    pub fn center() -> Anchor {
+        Anchor::new(AxisAnchor::Center(Length::Px(0)), AxisAnchor::Center(Length::Px(0)))
+    }
+
+    /// Stretched to the Layout with margins
+    pub fn fill(top: i32, bottom: i32, left: i32, right: i32) -> Anchor {
+        Anchor::new(
+            AxisAnchor::Both(Length::Px(left), Length::Px(right)),
+            AxisAnchor::Both(Length::Px(top), Length::Px(bottom)))
+    }
+
+    /// Set the size of the child
+    pub fn with_size(self, width: Length, height: Length) -> Anchor {
+        Anchor{ width: Some(width), height: Some(height), ..self }
+    }
+
+    /// Calculate the rect of the widget in the Layout's rect
+    fn place(&self, widget: &AnyWidget, rect: Rect) -> Rect {
+        let width = match self.x {
+            AxisAnchor::Both(start, end) => {
+                max(rect.width() as i32 - start.resolve(rect.width()) - end.resolve(rect.width()), 0) as u32
+            },
+            _ => match self.width {
+                Some(w) => max(w.resolve(rect.width()), 0) as u32,
+                None => widget.preferred_width().natural,
+            },
+        };
+        let height = match self.y {
+            AxisAnchor::Both(start, end) => {
+                max(rect.height() as i32 - start.resolve(rect.height()) - end.resolve(rect.height()), 0) as u32
+            },
+            _ => match self.height {
+                Some(h) => max(h.resolve(rect.height()), 0) as u32,
+                None => widget.preferred_height_for_width(width).natural,
+            },
+        };
+
+        let x = Anchor::position(self.x, rect.width(), width);
+        let y = Anchor::position(self.y, rect.height(), height);
+        Rect::new(rect.x() + x, rect.y() + y, width, height)
+    }
+
+    fn position(anchor: AxisAnchor, total: u32, size: u32) -> i32 {
+        match anchor {
+            AxisAnchor::Start(offset) | AxisAnchor::Both(offset, _) => offset.resolve(total),
+            AxisAnchor::End(offset) => total as i32 - offset.resolve(total) - size as i32,
+            AxisAnchor::Center(offset) => (total as i32 - size as i32) / 2 + offset.resolve(total),
+        }
+    }
+
+    /// Size of the Layout needed to show the child, ignoring percentages
+    fn needed_size(&self, widget: &AnyWidget) -> (u32, u32) {
+        let width = match self.width {
+            Some(w) => w.px_or_zero(),
+            None => widget.preferred_width().natural,
+        };
+        let height = match self.height {
+            Some(h) => h.px_or_zero(),
+            None => widget.preferred_height_for_width(width).natural,
+        };
+        let extent = |anchor: AxisAnchor, size: u32| match anchor {
+            AxisAnchor::Start(l) | AxisAnchor::End(l) | AxisAnchor::Center(l) => l.px_or_zero() + size,
+            AxisAnchor::Both(s, e) => s.px_or_zero() + e.px_or_zero() + size,
+        };
+        (extent(self.x, width), extent(self.y, height))
+    }
+}
+
+/// Where a child of Layout is placed
+#[derive(Clone, Copy)]
+enum Placement {
+    /// Fixed rect in the window, not moved with the Layout
+    Absolute(Rect),
+    /// Relative to the rect of the Layout
+    Anchored(Anchor),
+}
+
+/// Container which places children at fixed rects or by anchors
 pub struct Layout {
+    common: WidgetCommon,
     rect: Cell<Option<Rect>>,
-    children: RefCell<Vec<AnyWidget>>,
+    children: RefCell<Vec<(AnyWidget, Placement)>>,
 }
 
 impl Layout {
     pub fn new() -> Rc<Layout> {
         Rc::new(Layout{
+            common: WidgetCommon::new(),
             rect: Cell::new(None),
             children: RefCell::new(Vec::new())
         })
     }
 
+    /// Add a child at the rect in window coordinates.
+    /// Use add_anchored to place it relative to this Layout.
     pub fn add<T: Into<AnyWidget>>(&self, widget: T, rect: Rect) {
         let widget = widget.into();
-        widget.alloc_rect(rect);
-        self.children.borrow_mut().push(widget);
+        self.place_child(&widget, Placement::Absolute(rect));
+        self.children.borrow_mut().push((widget, Placement::Absolute(rect)));
+        request_redraw();
+    }
+
+    /// Add a child placed by the anchor. Anchor::fixed places it at a rect relative to this Layout.
+    pub fn add_anchored<T: Into<AnyWidget>>(&self, widget: T, anchor: Anchor) {
+        let widget = widget.into();
+        self.place_child(&widget, Placement::Anchored(anchor));
+        self.children.borrow_mut().push((widget, Placement::Anchored(anchor)));
+        request_redraw();
+    }
+
+    /// Change the anchor of the child
+    pub fn set_anchor<T: Into<AnyWidget>>(&self, widget: T, anchor: Anchor) {
+        let widget = widget.into();
+        let mut children = self.children.borrow_mut();
+        if let Some(c) = children.iter_mut().find(|c| c.0 == widget) {
+            c.1 = Placement::Anchored(anchor);
+            self.place_child(&c.0, c.1);
+            request_redraw();
+        }
+    }
+
+    /// Insert a child at the given position with the rect in window coordinates.
+    /// Children are rendered in order, so later children are drawn over earlier ones.
+    pub fn insert_at<T: Into<AnyWidget>>(&self, index: usize, widget: T, rect: Rect) {
+        let widget = widget.into();
+        let placement = Placement::Absolute(rect);
+        self.place_child(&widget, placement);
+        {
+            let mut children = self.children.borrow_mut();
+            let index = ::std::cmp::min(index, children.len());
+            children.insert(index, (widget, placement));
+        }
+        request_redraw();
+    }
+
+    /// Remove the child. Returns false if the widget is not a child of this layout.
+    pub fn remove<T: Into<AnyWidget>>(&self, widget: T) -> bool {
+        let widget = widget.into();
+        let mut children = self.children.borrow_mut();
+        if let Some(i) = children.iter().position(|c| c.0 == widget) {
+            children.remove(i);
+            request_redraw();
+            true
+        }else{
+            false
+        }
+    }
+
+    /// Move the child to the given position
+    pub fn reorder<T: Into<AnyWidget>>(&self, widget: T, index: usize) {
+        let widget = widget.into();
+        let mut children = self.children.borrow_mut();
+        if let Some(i) = children.iter().position(|c| c.0 == widget) {
+            let c = children.remove(i);
+            let index = ::std::cmp::min(index, children.len());
+            children.insert(index, c);
+            request_redraw();
+        }
+    }
+
+    /// Remove all children
+    pub fn clear(&self) {
+        self.children.borrow_mut().clear();
+        request_redraw();
+    }
+
+    // Absolute children are placed even before this Layout gets its rect
+    fn place_child(&self, widget: &AnyWidget, placement: Placement) {
+        match placement {
+            Placement::Absolute(rect) => widget.alloc_rect(rect),
+            Placement::Anchored(anchor) => {
+                if let Some(rect) = self.rect.get() {
+                    widget.alloc_rect(anchor.place(widget, rect));
+                }
+            },
+        }
+    }
+
+    // Size of this Layout needed to show the child
+    fn needed_size(&self, widget: &AnyWidget, placement: Placement) -> (u32, u32) {
+        match placement {
+            Placement::Absolute(r) => {
+                let (x, y) = self.rect.get().map_or((0, 0), |rect| (rect.x(), rect.y()));
+                (max(r.right() - x, 0) as u32, max(r.bottom() - y, 0) as u32)
+            },
+            Placement::Anchored(anchor) => anchor.needed_size(widget),
+        }
     }
 }
 
 impl WidgetTrait for Layout {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
     fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
         renderer.set_draw_color(get_background_color());
         try!(renderer.fill_rect(self.rect.get().unwrap()));
@@ -32,7 +287,7 @@ impl WidgetTrait for Layout {
         let n = self.children.borrow().len();
 
         for i in 0..n {
-            let child = self.children.borrow()[i].clone();
+            let child = self.children.borrow()[i].0.clone();
 
             try!(child.render(renderer));
         }
@@ -42,12 +297,38 @@ impl WidgetTrait for Layout {
 
     fn alloc_rect(&self, rect: Rect) {
         self.rect.set(Some(rect));
+
+        for &(ref widget, placement) in self.children.borrow().iter() {
+            self.place_child(widget, placement);
+        }
     }
 
     fn event_handler(&self, event: &Event) {
-        self.with_children(|w| {
+        for w in self.children() {
             w.event_handler(event);
-        });
+        }
+    }
+
+    fn cancel_input(&self) {
+        for c in self.children() {
+            c.cancel_input();
+        }
+    }
+
+    fn preferred_width(&self) -> SizeRange {
+        let mut width = 0;
+        for &(ref widget, placement) in self.children.borrow().iter() {
+            width = max(width, self.needed_size(widget, placement).0);
+        }
+        SizeRange::new(0, width)
+    }
+
+    fn preferred_height_for_width(&self, _width: u32) -> SizeRange {
+        let mut height = 0;
+        for &(ref widget, placement) in self.children.borrow().iter() {
+            height = max(height, self.needed_size(widget, placement).1);
+        }
+        SizeRange::new(0, height)
     }
 }
 
@@ -55,7 +336,7 @@ impl ContainerTrait for Layout {
     fn with_children<F: FnMut(&AnyWidget)>(&self, f: F) {
         let mut f = f;
         for w in self.children.borrow().iter() {
-            f(w)
+            f(&w.0)
         }
     }
 }
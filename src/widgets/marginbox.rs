@@ -2,8 +2,11 @@ use std::rc::Rc;
 use std::cell::{RefCell, Cell};
 
 use prelude::*;
+use widgets::aligned_rect;
+use uicontext::request_relayout;
 
 pub struct MarginBox {
+    common: WidgetCommon,
     rect: Cell<Option<Rect>>,
     child: RefCell<Option<AnyWidget>>,
     top: Cell<u32>,
@@ -16,6 +19,7 @@ impl MarginBox {
     pub fn new<W: Into<AnyWidget>>(
         w: W, top: u32, bottom: u32, left: u32, right: u32) -> Rc<MarginBox> {
         Rc::new(MarginBox{
+            common: WidgetCommon::new(),
             rect: Cell::new(None),
             child: RefCell::new(Some(w.into())),
             top: Cell::new(top),
@@ -27,6 +31,7 @@ impl MarginBox {
 
     pub fn empty(top: u32, bottom: u32, left: u32, right: u32) -> Rc<MarginBox> {
         Rc::new(MarginBox{
+            common: WidgetCommon::new(),
             rect: Cell::new(None),
             child: RefCell::new(None),
             top: Cell::new(top),
@@ -39,10 +44,44 @@ impl MarginBox {
     pub fn set_child<W: Into<AnyWidget>>(&self, w: W) {
         let w = w.into();
         *self.child.borrow_mut() = Some(w);
+        request_relayout();
+    }
+
+    /// Remove the child. Returns false if the widget is not the child.
+    pub fn remove<W: Into<AnyWidget>>(&self, w: W) -> bool {
+        let w = w.into();
+        let is_child = self.child.borrow().as_ref() == Some(&w);
+        if is_child {
+            self.clear();
+        }
+        is_child
+    }
+
+    /// Remove the child if exists
+    pub fn clear(&self) {
+        *self.child.borrow_mut() = None;
+        request_relayout();
+    }
+
+    pub fn set_margins(&self, top: u32, bottom: u32, left: u32, right: u32) {
+        self.top.set(top);
+        self.bottom.set(bottom);
+        self.left.set(left);
+        self.right.set(right);
+        request_relayout();
+    }
+
+    /// Returns (top, bottom, left, right)
+    pub fn get_margins(&self) -> (u32, u32, u32, u32) {
+        (self.top.get(), self.bottom.get(), self.left.get(), self.right.get())
     }
 }
 
 impl WidgetTrait for MarginBox {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
     fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
         let child = self.child.borrow();
         if let Some(ref c) = *child {
@@ -72,35 +111,40 @@ impl WidgetTrait for MarginBox {
                 bottom = 0;
             }
             
-            c.alloc_rect(Rect::new(
+            c.alloc_rect(aligned_rect(c, Rect::new(
                 rect.x() + left as i32,
                 rect.y() + top as i32,
                 rect.width() - left - right,
                 rect.height() - top - bottom,
-            ));
+            )));
         }
     }
 
     fn event_handler(&self, event: &Event) {
-        self.with_child(|w| {
+        for w in self.children() {
             w.event_handler(event);
-        });
+        }
     }
 
-    fn requested_size(&self) -> Option<(u32, u32)> {
-        let child = self.child.borrow();
-
-        if let Some(ref c) = *child {
-            let size = c.requested_size();
-            if let Some(size) = size {
-                Some((size.0 + self.left.get() + self.right.get(), size.1 + self.top.get() + self.bottom.get()))
-            }else{
-                Some((self.left.get() + self.right.get(), self.top.get() + self.bottom.get()))
-            }
-        }else{
-            Some((self.left.get() + self.right.get(), self.top.get() + self.bottom.get()))
+    fn cancel_input(&self) {
+        for c in self.children() {
+            c.cancel_input();
         }
     }
+
+    fn preferred_width(&self) -> SizeRange {
+        let horizontal_margin = self.left.get() + self.right.get();
+        let size = self.with_child(|c| c.preferred_width()).unwrap_or_default();
+        size.add(horizontal_margin)
+    }
+
+    fn preferred_height_for_width(&self, width: u32) -> SizeRange {
+        let horizontal_margin = self.left.get() + self.right.get();
+        let vertical_margin = self.top.get() + self.bottom.get();
+        let child_width = if width > horizontal_margin { width - horizontal_margin }else{ 0 };
+        let size = self.with_child(|c| c.preferred_height_for_width(child_width)).unwrap_or_default();
+        size.add(vertical_margin)
+    }
 }
 
 impl ContainerTrait for MarginBox {
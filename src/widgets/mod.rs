@@ -1,5 +1,6 @@
 
 use std::rc::{Rc, Weak};
+use std::cmp::min;
 
 use prelude::*;
 
@@ -7,6 +8,8 @@ mod frame;
 mod empty;
 mod layout;
 mod button;
+mod checkbutton;
+mod radiobutton;
 mod label;
 mod vbox;
 mod hbox;
@@ -14,10 +17,28 @@ mod drawingarea;
 mod marginbox;
 mod centeringbox;
 mod scrollbar;
+mod scale;
+mod entry;
+mod textview;
+mod notebook;
+mod paned;
+mod scrolledwindow;
+mod stack;
+mod flowbox;
+mod overlay;
+mod expander;
+mod constraintlayout;
+mod inspector;
+mod boxlayout;
+mod debugoverlay;
+mod textutil;
+mod common;
 
 pub use self::empty::Empty;
-pub use self::layout::Layout;
+pub use self::layout::{Layout, Anchor, AxisAnchor, Length};
 pub use self::button::Button;
+pub use self::checkbutton::CheckButton;
+pub use self::radiobutton::{RadioButton, RadioGroup};
 pub use self::label::Label;
 pub use self::frame::Frame;
 pub use self::vbox::VBox;
@@ -25,7 +46,21 @@ pub use self::hbox::HBox;
 pub use self::drawingarea::DrawingArea;
 pub use self::marginbox::MarginBox;
 pub use self::centeringbox::CenteringBox;
-pub use self::scrollbar::HScrollBar;
+pub use self::scrollbar::{HScrollBar, VScrollBar};
+pub use self::scale::{HScale, VScale};
+pub use self::entry::Entry;
+pub use self::textview::{TextView, TextBuffer, TextPos};
+pub use self::notebook::Notebook;
+pub use self::paned::{HPaned, VPaned};
+pub use self::scrolledwindow::ScrolledWindow;
+pub use self::stack::Stack;
+pub use self::flowbox::FlowBox;
+pub use self::overlay::Overlay;
+pub use self::expander::Expander;
+pub use self::constraintlayout::{ConstraintLayout, ConstraintVars};
+pub use self::inspector::Inspector;
+pub use self::common::{WidgetCommon, aligned_rect, rendering_insensitive};
+use self::common::render_with_sensitivity;
 
 macro_rules! impl_anywidget {
     ( $($i:ident),* ) => {
@@ -47,30 +82,104 @@ macro_rules! impl_anywidget {
                     )*
                 }
             }
+
+            /// Name of the widget type
+            pub fn type_name(&self) -> &'static str {
+                match *self { $( AnyWidget::$i(_) => stringify!($i), )* }
+            }
+
+            /// Get the concrete widget if this is a T
+            pub fn downcast<T: WidgetType>(&self) -> Option<Rc<T>> {
+                T::from_any(self)
+            }
         }
 
+        /// Implemented by the widget types which AnyWidget can hold
+        pub trait WidgetType: Sized {
+            fn from_any(w: &AnyWidget) -> Option<Rc<Self>>;
+        }
+
+        $(
+            impl WidgetType for $i {
+                fn from_any(w: &AnyWidget) -> Option<Rc<$i>> {
+                    if let AnyWidget::$i(ref w) = *w {
+                        Some(w.clone())
+                    }else{
+                        None
+                    }
+                }
+            }
+        )*
+
         /// AnyWidget can call methods of WidgetTrait directly.
         impl WidgetTrait for AnyWidget {
             fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
+                if !self.get_visible() {
+                    return Ok(());
+                }
+                render_with_sensitivity(self.common(), renderer, |renderer| {
+                    match *self { $( AnyWidget::$i(ref w) => {
+                        w.render(renderer)
+                    }, )* }
+                })
+            }
+            fn common(&self) -> &WidgetCommon {
                 match *self { $( AnyWidget::$i(ref w) => {
-                    return w.render(renderer);
+                    return w.common();
                 }, )* }
             }
             fn alloc_rect(&self, rect: Rect) {
+                if !self.get_visible() {
+                    self.common().allocation.set(None);
+                    return;
+                }
+                let rect = self.common().constrain_rect(rect);
+                self.common().allocation.set(Some(rect));
                 match *self { $( AnyWidget::$i(ref w) => {
                     w.alloc_rect(rect);
                 }, )* }
             }
             fn event_handler(&self, event: &Event) {
+                if !self.get_visible() || (!self.get_sensitive() && event.is_input()) {
+                    return;
+                }
+                if event.is_key() && !::uicontext::on_focus_path(self) {
+                    return;
+                }
                 match *self { $( AnyWidget::$i(ref w) => {
                     w.event_handler(event);
                 }, )* }
             }
-            fn requested_size(&self) -> Option<(u32, u32)> {
+            fn cancel_input(&self) {
                 match *self { $( AnyWidget::$i(ref w) => {
-                    return w.requested_size();
+                    w.cancel_input();
                 }, )* }
             }
+            fn preferred_width(&self) -> SizeRange {
+                if !self.get_visible() {
+                    return SizeRange::default();
+                }
+                let size = match *self { $( AnyWidget::$i(ref w) => {
+                    w.preferred_width()
+                }, )* };
+                self.common().constrain_width(size)
+            }
+            fn preferred_height_for_width(&self, width: u32) -> SizeRange {
+                if !self.get_visible() {
+                    return SizeRange::default();
+                }
+                // The widget gets the width limited by its maximum width
+                let common = self.common();
+                let width = common.max_width_limit().map_or(width, |m| min(width, m));
+                let size = if let Some(ratio) = common.aspect_ratio.get().filter(|r| *r > 0.0) {
+                    SizeRange::fixed((width as f64 / ratio).round() as u32)
+                }else{
+                    match *self { $( AnyWidget::$i(ref w) => {
+                        w.preferred_height_for_width(width)
+                    }, )* }
+                };
+                common.constrain_height(size)
+            }
         }
 
         impl PartialEq for AnyWidget {
@@ -119,10 +228,29 @@ macro_rules! impl_anywidget {
     }
 }
 
+macro_rules! impl_anywidget_container {
+    ( $($i:ident),* ) => {
+        /// Widgets which are not containers have no children
+        impl ContainerTrait for AnyWidget {
+            fn with_children<F: FnMut(&AnyWidget)>(&self, f: F) {
+                match *self {
+                    $( AnyWidget::$i(ref w) => { w.with_children(f); }, )*
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
 impl_anywidget!(
     Frame,
-    Layout, VBox, HBox,
-    MarginBox, CenteringBox,
-    Button, Empty, Label, DrawingArea,
-    HScrollBar);
+    Layout, ConstraintLayout, VBox, HBox, FlowBox, Notebook, HPaned, VPaned, Stack, Overlay,
+    MarginBox, CenteringBox, ScrolledWindow, Expander, Inspector,
+    Button, CheckButton, RadioButton, Empty, Label, Entry, TextView, DrawingArea,
+    HScrollBar, VScrollBar, HScale, VScale);
 
+impl_anywidget_container!(
+    Frame,
+    Layout, ConstraintLayout, VBox, HBox, FlowBox, Notebook, HPaned, VPaned, Stack, Overlay,
+    MarginBox, CenteringBox, ScrolledWindow, Expander, Inspector,
+    Button, CheckButton, RadioButton);
@@ -0,0 +1,333 @@
+
+use std::rc::Rc;
+use std::cell::{RefCell, Cell};
+use std::cmp::max;
+use ::sdl2::mouse::Mouse;
+use ::sdl2::keyboard::Keycode;
+
+use prelude::*;
+use theme;
+use event::{ctrl_pressed, shift_pressed};
+use widgets::aligned_rect;
+use uicontext::{request_redraw, request_relayout};
+
+const TAB_PADDING: u32 = 4;
+
+struct Page {
+    child: AnyWidget,
+    tab: AnyWidget,
+    tab_rect: Cell<Option<Rect>>,
+}
+
+/// Container which has pages switched by tabs
+pub struct Notebook {
+    common: WidgetCommon,
+    rect: Cell<Option<Rect>>,
+    pages: RefCell<Vec<Page>>,
+    current: Cell<usize>,
+    callback_page_changed: RefCell<Option<Rc<Fn(usize) + 'static>>>,
+    layout_updated: Cell<bool>,
+}
+
+impl Notebook {
+    pub fn new() -> Rc<Notebook> {
+        Rc::new(Notebook{
+            common: WidgetCommon::new(),
+            rect: Cell::new(None),
+            pages: RefCell::new(Vec::new()),
+            current: Cell::new(0),
+            callback_page_changed: RefCell::new(None),
+            layout_updated: Cell::new(false),
+        })
+    }
+
+    /// Add a page with a tab label widget. Returns the index of the new page.
+    pub fn append_page<W: Into<AnyWidget>, T: Into<AnyWidget>>(&self, child: W, tab: T) -> usize {
+        let n = self.n_pages();
+        self.insert_page(n, child, tab);
+        n
+    }
+
+    /// Add a page with a text tab label. Returns the index of the new page.
+    pub fn append_page_with_label<W: Into<AnyWidget>>(&self, child: W, label: &str) -> usize {
+        self.append_page(child, ::widgets::Label::new(label))
+    }
+
+    /// Insert a page at the given position
+    pub fn insert_page<W: Into<AnyWidget>, T: Into<AnyWidget>>(&self, index: usize, child: W, tab: T) {
+        {
+            let mut pages = self.pages.borrow_mut();
+            let index = ::std::cmp::min(index, pages.len());
+            pages.insert(index, Page{
+                child: child.into(),
+                tab: tab.into(),
+                tab_rect: Cell::new(None),
+            });
+            if index <= self.current.get() && pages.len() > 1 {
+                self.current.set(self.current.get() + 1);
+            }
+        }
+        self.layout_updated.set(false);
+        request_relayout();
+    }
+
+    /// Remove the page at the given position
+    /// The page changed callback is called if the shown page or its index is changed.
+    pub fn remove_page(&self, index: usize) {
+        let changed = {
+            let mut pages = self.pages.borrow_mut();
+            if index >= pages.len() {
+                return;
+            }
+            pages.remove(index);
+            let current = self.current.get();
+            if index < current || (current == pages.len() && current > 0) {
+                self.current.set(current - 1);
+            }
+            index <= current && !pages.is_empty()
+        };
+        self.layout_updated.set(false);
+        request_relayout();
+
+        if changed {
+            let callback = self.callback_page_changed.borrow().clone();
+            if let Some(f) = callback {
+                f(self.current.get());
+            }
+        }
+    }
+
+    pub fn n_pages(&self) -> usize {
+        self.pages.borrow().len()
+    }
+
+    /// Switch to the page at the given position
+    pub fn set_current_page(&self, index: usize) {
+        if index >= self.n_pages() || index == self.current.get() {
+            return;
+        }
+        self.current.set(index);
+        request_redraw();
+        
+        let callback = self.callback_page_changed.borrow().clone();
+        if let Some(f) = callback {
+            f(index);
+        }
+    }
+
+    pub fn get_current_page(&self) -> usize {
+        self.current.get()
+    }
+
+    /// Switch to the next page. The first page follows the last page.
+    pub fn next_page(&self) {
+        let n = self.n_pages();
+        if n > 0 {
+            self.set_current_page((self.current.get() + 1) % n);
+        }
+    }
+
+    /// Switch to the previous page. The last page follows the first page.
+    pub fn prev_page(&self) {
+        let n = self.n_pages();
+        if n > 0 {
+            self.set_current_page((self.current.get() + n - 1) % n);
+        }
+    }
+
+    /// Set a callback called with the new page index when the current page is changed
+    pub fn on_page_changed<F>(&self, f: F) where F: Fn(usize) + 'static {
+        *self.callback_page_changed.borrow_mut() = Some(Rc::new(f));
+    }
+
+    /// The child of the current page
+    pub fn current_page_widget(&self) -> Option<AnyWidget> {
+        self.pages.borrow().get(self.current.get()).map(|page| page.child.clone())
+    }
+
+    /// Tab label widgets of all pages in order
+    pub fn tab_widgets(&self) -> Vec<AnyWidget> {
+        self.pages.borrow().iter().map(|page| page.tab.clone()).collect()
+    }
+
+    fn tab_height(&self) -> u32 {
+        let mut height = 0;
+        for page in self.pages.borrow().iter() {
+            let width = page.tab.preferred_width().natural;
+            height = max(height, page.tab.preferred_height_for_width(width).natural);
+        }
+        height + TAB_PADDING * 2
+    }
+
+    fn page_rect(&self, rect: Rect, tab_height: u32) -> Rect {
+        let width = if rect.width() > 2 { rect.width() - 2 }else{ 0 };
+        let height = if rect.height() > tab_height + 2 { rect.height() - tab_height - 2 }else{ 0 };
+        Rect::new(rect.x() + 1, rect.y() + tab_height as i32 + 1, width, height)
+    }
+
+    fn position_update(&self) {
+        let rect = match self.rect.get() {
+            Some(rect) => rect,
+            None => { return; },
+        };
+
+        let tab_height = self.tab_height();
+        let page_rect = self.page_rect(rect, tab_height);
+        let mut x = rect.x();
+
+        for page in self.pages.borrow().iter() {
+            let width = page.tab.preferred_width().natural + TAB_PADDING * 2;
+            let tab_rect = Rect::new(x, rect.y(), width, tab_height);
+            page.tab_rect.set(Some(tab_rect));
+            page.tab.alloc_rect(Rect::new(
+                x + TAB_PADDING as i32, rect.y() + TAB_PADDING as i32,
+                width - TAB_PADDING * 2, tab_height - TAB_PADDING * 2));
+            x += width as i32;
+
+            page.child.alloc_rect(aligned_rect(&page.child, page_rect));
+        }
+
+        self.layout_updated.set(true);
+    }
+}
+
+impl WidgetTrait for Notebook {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
+    fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
+        if !self.layout_updated.get() {
+            self.position_update();
+        }
+
+        let rect = self.rect.get().expect("No rectangle allocation at rendering");
+        let color = theme::get_theme_color();
+        let tab_height = self.tab_height();
+        let current = self.current.get();
+
+        // Render the border of pages
+        let page_border = Rect::new(
+            rect.x(), rect.y() + tab_height as i32 - 1, rect.width(),
+            if rect.height() > tab_height { rect.height() - tab_height + 1 }else{ 1 });
+        renderer.set_draw_color(color.dark_border);
+        try!(renderer.draw_rect(page_border));
+
+        // Render tabs
+        for (i, page) in self.pages.borrow().iter().enumerate() {
+            let tab_rect = match page.tab_rect.get() {
+                Some(r) => r,
+                None => continue,
+            };
+            let left = tab_rect.x();
+            let right = tab_rect.x() + tab_rect.width() as i32 - 1;
+            let top = tab_rect.y();
+            let bottom = tab_rect.y() + tab_rect.height() as i32 - 1;
+
+            if i == current {
+                renderer.set_draw_color(color.background);
+                try!(renderer.fill_rect(tab_rect));
+            }else{
+                renderer.set_draw_color(color.dark_background);
+                try!(renderer.fill_rect(Rect::new(
+                    left, top + 2, tab_rect.width(), if tab_rect.height() > 3 { tab_rect.height() - 3 }else{ 1 })));
+            }
+            
+            let top = if i == current { top }else{ top + 2 };
+            renderer.set_draw_color(color.light_border);
+            try!(renderer.draw_lines(&[
+                Point::new(left, bottom), Point::new(left, top), Point::new(right, top),
+            ]));
+            renderer.set_draw_color(color.dark_border);
+            try!(renderer.draw_line(Point::new(right, top), Point::new(right, bottom)));
+
+            try!(page.tab.render(renderer));
+        }
+
+        // Render the current page
+        if let Some(child) = self.current_page_widget() {
+            let before_clip = renderer.clip_rect();
+            renderer.set_clip_rect(Some(self.page_rect(rect, tab_height)));
+            let result = child.render(renderer);
+            renderer.set_clip_rect(before_clip);
+            try!(result);
+        }
+
+        Ok(())
+    }
+
+    fn alloc_rect(&self, rect: Rect) {
+        self.rect.set(Some(rect));
+        self.layout_updated.set(false);
+    }
+
+    fn event_handler(&self, event: &Event) {
+        match *event {
+            Event::ButtonDown((x, y), mouse_btn) => {
+                let inside = self.rect.get().map_or(false, |r| r.contains((x, y)));
+                if inside {
+                    // A focusable widget in the page takes the focus from the notebook
+                    self.grab_focus();
+                }
+
+                if inside && mouse_btn == Mouse::Left {
+                    let clicked_tab = self.pages.borrow().iter().position(|page| {
+                        page.tab_rect.get().map_or(false, |r| r.contains((x, y)))
+                    });
+                    if let Some(i) = clicked_tab {
+                        self.set_current_page(i);
+                    }
+                }
+            },
+            Event::KeyDown(Keycode::Tab, keymod) if ctrl_pressed(keymod) && self.contains_focus() => {
+                if shift_pressed(keymod) {
+                    self.prev_page();
+                }else{
+                    self.next_page();
+                }
+                return;
+            },
+            _ => (),
+        }
+
+        if let Some(child) = self.current_page_widget() {
+            child.event_handler(event);
+        }
+    }
+
+    fn cancel_input(&self) {
+        for c in self.children() {
+            c.cancel_input();
+        }
+    }
+
+    fn preferred_width(&self) -> SizeRange {
+        let mut tabs_width = 0;
+        let mut size = SizeRange::default();
+        for page in self.pages.borrow().iter() {
+            tabs_width += page.tab.preferred_width().natural + TAB_PADDING * 2;
+            size = size.max(page.child.preferred_width());
+        }
+        size.add(2).max(SizeRange::fixed(tabs_width))
+    }
+
+    fn preferred_height_for_width(&self, width: u32) -> SizeRange {
+        let child_width = if width > 2 { width - 2 }else{ 0 };
+        let mut size = SizeRange::default();
+        for page in self.pages.borrow().iter() {
+            size = size.max(page.child.preferred_height_for_width(child_width));
+        }
+        size.add(self.tab_height() + 2)
+    }
+}
+
+impl ContainerTrait for Notebook {
+    fn with_children<F: FnMut(&AnyWidget)>(&self, f: F) {
+        let mut f = f;
+
+        for page in self.pages.borrow().iter() {
+            f(&page.tab);
+            f(&page.child);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+
+use std::rc::Rc;
+use std::cell::{RefCell, Cell};
+
+use prelude::*;
+use widgets::aligned_rect;
+use uicontext::request_relayout;
+
+struct OverlayChild {
+    widget: AnyWidget,
+    offset: Cell<(i32, i32)>,
+    rect: Cell<Option<Rect>>,
+}
+
+/// Container which shows overlay children over its main child.
+/// Overlay children are placed in the main child's rect by their alignment and offsets.
+pub struct Overlay {
+    common: WidgetCommon,
+    rect: Cell<Option<Rect>>,
+    child: RefCell<Option<AnyWidget>>,
+    overlays: RefCell<Vec<OverlayChild>>, // The last one is the top-most
+}
+
+impl Overlay {
+    pub fn new() -> Rc<Overlay> {
+        Rc::new(Overlay{
+            common: WidgetCommon::new(),
+            rect: Cell::new(None),
+            child: RefCell::new(None),
+            overlays: RefCell::new(Vec::new()),
+        })
+    }
+
+    /// Set the main child
+    pub fn set_child<W: Into<AnyWidget>>(&self, w: W) {
+        *self.child.borrow_mut() = Some(w.into());
+        request_relayout();
+    }
+
+    /// Add an overlay child on the top
+    pub fn add_overlay<W: Into<AnyWidget>>(&self, w: W) {
+        self.add_overlay_with_offset(w, 0, 0);
+    }
+
+    /// Add an overlay child on the top, shifted from its aligned position by the offset
+    pub fn add_overlay_with_offset<W: Into<AnyWidget>>(&self, w: W, x: i32, y: i32) {
+        self.overlays.borrow_mut().push(OverlayChild{
+            widget: w.into(),
+            offset: Cell::new((x, y)),
+            rect: Cell::new(None),
+        });
+        request_relayout();
+    }
+
+    /// Change the offset of an overlay child
+    pub fn set_overlay_offset<W: Into<AnyWidget>>(&self, w: W, x: i32, y: i32) {
+        let w = w.into();
+        if let Some(o) = self.overlays.borrow().iter().find(|o| o.widget == w) {
+            o.offset.set((x, y));
+        }
+        request_relayout();
+    }
+
+    /// Remove an overlay child. Returns false if the widget is not an overlay child.
+    pub fn remove_overlay<W: Into<AnyWidget>>(&self, w: W) -> bool {
+        let w = w.into();
+        let mut overlays = self.overlays.borrow_mut();
+        match overlays.iter().position(|o| o.widget == w) {
+            Some(i) => {
+                overlays.remove(i);
+                request_relayout();
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Move an overlay child to the given position in the stacking order. 0 is the bottom.
+    pub fn reorder_overlay<W: Into<AnyWidget>>(&self, w: W, index: usize) {
+        let w = w.into();
+        let mut overlays = self.overlays.borrow_mut();
+        if let Some(i) = overlays.iter().position(|o| o.widget == w) {
+            let o = overlays.remove(i);
+            let index = ::std::cmp::min(index, overlays.len());
+            overlays.insert(index, o);
+            request_relayout();
+        }
+    }
+
+    /// Rect of the main child where overlay children are placed
+    fn main_rect(&self, rect: Rect) -> Rect {
+        match *self.child.borrow() {
+            Some(ref c) => aligned_rect(c, rect),
+            None => rect,
+        }
+    }
+}
+
+impl WidgetTrait for Overlay {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
+    fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
+        if let Some(ref c) = *self.child.borrow() {
+            try!(c.render(renderer));
+        }
+        for o in self.overlays.borrow().iter() {
+            try!(o.widget.render(renderer));
+        }
+        Ok(())
+    }
+
+    fn alloc_rect(&self, rect: Rect) {
+        self.rect.set(Some(rect));
+
+        let main_rect = self.main_rect(rect);
+        if let Some(ref c) = *self.child.borrow() {
+            c.alloc_rect(main_rect);
+        }
+        for o in self.overlays.borrow().iter() {
+            // A hidden overlay must not cover the child
+            if !o.widget.get_visible() {
+                o.rect.set(None);
+                continue;
+            }
+            let mut r = aligned_rect(&o.widget, main_rect);
+            let (x, y) = o.offset.get();
+            r.offset(x, y);
+            o.widget.alloc_rect(r);
+            o.rect.set(Some(r));
+        }
+    }
+
+    fn event_handler(&self, event: &Event) {
+        // Clicks and wheel scrolls are delivered only down to the top-most child under the pointer
+        let pointer = match *event {
+            Event::ButtonDown(p, _) | Event::MouseWheel(p, _) => Some(p),
+            _ => None,
+        };
+
+        let overlays: Vec<(AnyWidget, Option<Rect>)> = self.overlays.borrow().iter().rev()
+            .map(|o| (o.widget.clone(), o.rect.get())).collect();
+        for (w, rect) in overlays {
+            if !w.get_visible() {
+                continue;
+            }
+            w.event_handler(event);
+            if let (Some(p), Some(rect)) = (pointer, rect) {
+                if rect.contains(p) {
+                    return;
+                }
+            }
+        }
+
+        let child = self.child.borrow().clone();
+        if let Some(c) = child {
+            c.event_handler(event);
+        }
+    }
+
+    fn cancel_input(&self) {
+        for c in self.children() {
+            c.cancel_input();
+        }
+    }
+
+    fn preferred_width(&self) -> SizeRange {
+        match *self.child.borrow() {
+            Some(ref c) => c.preferred_width(),
+            None => SizeRange::default(),
+        }
+    }
+
+    fn preferred_height_for_width(&self, width: u32) -> SizeRange {
+        match *self.child.borrow() {
+            Some(ref c) => c.preferred_height_for_width(width),
+            None => SizeRange::default(),
+        }
+    }
+}
+
+impl ContainerTrait for Overlay {
+    fn with_children<F: FnMut(&AnyWidget)>(&self, f: F) {
+        let mut f = f;
+
+        if let Some(ref c) = *self.child.borrow() {
+            f(c);
+        }
+        for o in self.overlays.borrow().iter() {
+            f(&o.widget);
+        }
+    }
+}
@@ -0,0 +1,409 @@
+
+use std::rc::Rc;
+use std::cell::{RefCell, Cell};
+use std::cmp::{min, max};
+use ::sdl2::mouse::Mouse;
+use ::sdl2::keyboard::Keycode;
+
+use prelude::*;
+use theme;
+use enums::Direction;
+use event::ctrl_pressed;
+use widgets::aligned_rect;
+use uicontext::{request_redraw, request_focus};
+
+const DIVIDER_SIZE: u32 = 6;
+const NUDGE_STEP: u32 = 10;
+
+/// A child of a paned widget
+struct PaneChild {
+    widget: RefCell<Option<AnyWidget>>,
+    shrink: Cell<bool>, // The child can be smaller than its minimum size
+    min_size: Cell<u32>,
+}
+
+impl PaneChild {
+    fn new() -> PaneChild {
+        PaneChild{
+            widget: RefCell::new(None),
+            shrink: Cell::new(true),
+            min_size: Cell::new(0),
+        }
+    }
+
+    fn widget(&self) -> Option<AnyWidget> {
+        self.widget.borrow().clone()
+    }
+
+    /// Size of the child along the divided direction
+    fn size(&self, direction: Direction) -> SizeRange {
+        let size = match self.widget() {
+            Some(w) => match direction {
+                Direction::Horizon => w.preferred_width(),
+                Direction::Vertical => w.preferred_height_for_width(w.preferred_width().natural),
+            },
+            None => SizeRange::default(),
+        };
+        if self.shrink.get() {
+            SizeRange::new(self.min_size.get(), size.natural)
+        }else{
+            SizeRange::new(max(self.min_size.get(), size.minimum), size.natural)
+        }
+    }
+}
+
+/// Common implementation of HPaned and VPaned
+struct PanedBase {
+    common: WidgetCommon,
+    direction: Direction,
+    rect: Cell<Option<Rect>>,
+    first: PaneChild,
+    second: PaneChild,
+    position: Cell<Option<u32>>, // If None, the natural size of the first child is used
+    drag_offset: Cell<Option<i32>>, // Distance between the pointer and the divider while dragging
+    callback_position_changed: RefCell<Option<Rc<Fn(u32) + 'static>>>,
+    layout_updated: Cell<bool>,
+}
+
+impl PanedBase {
+    fn new(direction: Direction) -> PanedBase {
+        PanedBase{
+            common: WidgetCommon::new(),
+            direction: direction,
+            rect: Cell::new(None),
+            first: PaneChild::new(),
+            second: PaneChild::new(),
+            position: Cell::new(None),
+            drag_offset: Cell::new(None),
+            callback_position_changed: RefCell::new(None),
+            layout_updated: Cell::new(false),
+        }
+    }
+
+    fn length(&self, rect: Rect) -> u32 {
+        match self.direction {
+            Direction::Horizon => rect.width(),
+            Direction::Vertical => rect.height(),
+        }
+    }
+
+    fn start(&self, rect: Rect) -> i32 {
+        match self.direction {
+            Direction::Horizon => rect.x(),
+            Direction::Vertical => rect.y(),
+        }
+    }
+
+    fn pos_on_axis(&self, p: (i32, i32)) -> i32 {
+        match self.direction {
+            Direction::Horizon => p.0,
+            Direction::Vertical => p.1,
+        }
+    }
+
+    /// Cut a part of the rect along the divided direction
+    fn sub_rect(&self, rect: Rect, offset: u32, length: u32) -> Rect {
+        match self.direction {
+            Direction::Horizon => Rect::new(rect.x() + offset as i32, rect.y(), length, rect.height()),
+            Direction::Vertical => Rect::new(rect.x(), rect.y() + offset as i32, rect.width(), length),
+        }
+    }
+
+    /// Clamp the position by the minimum sizes of children
+    fn clamp_position(&self, position: u32, length: u32) -> u32 {
+        let available = if length > DIVIDER_SIZE { length - DIVIDER_SIZE }else{ 0 };
+        let lower = self.first.size(self.direction).minimum;
+        let second_min = self.second.size(self.direction).minimum;
+        let upper = if available > second_min { available - second_min }else{ 0 };
+
+        min(max(position, lower), max(upper, lower)).min(available)
+    }
+
+    /// Current position of the divider in the allocated rect
+    fn current_position(&self) -> u32 {
+        let length = self.rect.get().map_or(0, |r| self.length(r));
+        let position = match self.position.get() {
+            Some(p) => p,
+            None => self.first.size(self.direction).natural,
+        };
+        self.clamp_position(position, length)
+    }
+
+    fn divider_rect(&self) -> Option<Rect> {
+        self.rect.get().map(|rect| self.sub_rect(rect, self.current_position(), DIVIDER_SIZE))
+    }
+
+    fn set_position(&self, position: u32) {
+        let length = self.rect.get().map_or_else(|| position.saturating_add(DIVIDER_SIZE), |r| self.length(r));
+        let position = self.clamp_position(position, length);
+        if self.position.get() == Some(position) {
+            return;
+        }
+        
+        self.position.set(Some(position));
+        self.layout_updated.set(false);
+        request_redraw();
+
+        let callback = self.callback_position_changed.borrow().clone();
+        if let Some(f) = callback {
+            f(position);
+        }
+    }
+
+    fn nudge(&self, forward: bool, step: u32) {
+        let position = self.current_position();
+        let position = if forward {
+            position + step
+        }else if position > step {
+            position - step
+        }else{
+            0
+        };
+        self.set_position(position);
+    }
+
+    fn position_update(&self) {
+        let rect = match self.rect.get() {
+            Some(rect) => rect,
+            None => { return; },
+        };
+        
+        let length = self.length(rect);
+        let position = self.current_position();
+        let second_start = min(position + DIVIDER_SIZE, length);
+
+        if let Some(w) = self.first.widget() {
+            w.alloc_rect(aligned_rect(&w, self.sub_rect(rect, 0, position)));
+        }
+        if let Some(w) = self.second.widget() {
+            w.alloc_rect(aligned_rect(&w, self.sub_rect(rect, second_start, length - second_start)));
+        }
+        self.layout_updated.set(true);
+    }
+
+    fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
+        if !self.layout_updated.get() {
+            self.position_update();
+        }
+
+        let rect = self.rect.get().expect("No rectangle allocation at rendering");
+        let position = self.current_position();
+        let length = self.length(rect);
+        let second_start = min(position + DIVIDER_SIZE, length);
+        let before_clip = renderer.clip_rect();
+
+        for &(ref child, offset, length) in &[
+            (&self.first, 0, position), (&self.second, second_start, length - second_start)] {
+            if let Some(w) = child.widget() {
+                renderer.set_clip_rect(Some(self.sub_rect(rect, offset, length)));
+                let result = w.render(renderer);
+                renderer.set_clip_rect(before_clip);
+                try!(result);
+            }
+        }
+
+        // Render the divider
+        let color = theme::get_theme_color();
+        let divider = self.sub_rect(rect, position, DIVIDER_SIZE);
+        renderer.set_draw_color(color.background);
+        try!(renderer.fill_rect(divider));
+        
+        let (light_start, light_end, dark_start, dark_end) = match self.direction {
+            Direction::Horizon => (
+                divider.top_left(), divider.bottom_left().offset(0, -1),
+                divider.top_right().offset(-1, 0), divider.bottom_right().offset(-1, -1)),
+            Direction::Vertical => (
+                divider.top_left(), divider.top_right().offset(-1, 0),
+                divider.bottom_left().offset(0, -1), divider.bottom_right().offset(-1, -1)),
+        };
+        renderer.set_draw_color(color.light_border);
+        try!(renderer.draw_line(light_start, light_end));
+        renderer.set_draw_color(color.dark_border);
+        try!(renderer.draw_line(dark_start, dark_end));
+
+        Ok(())
+    }
+
+    fn event_handler(&self, event: &Event) {
+        match *event {
+            Event::ButtonDown((x, y), Mouse::Left) => {
+                let on_divider = self.divider_rect().map_or(false, |r| r.contains((x, y)));
+                if on_divider {
+                    // Same as grab_focus of the paned widget
+                    self.common.focus_requested.set(true);
+                    request_focus();
+                    let divider_start = self.start(self.divider_rect().unwrap());
+                    self.drag_offset.set(Some(self.pos_on_axis((x, y)) - divider_start));
+                }
+            },
+            Event::ButtonUp(_, Mouse::Left) | Event::WindowFocusLost => {
+                self.drag_offset.set(None);
+            },
+            Event::MouseMotion(p) => {
+                if let (Some(offset), Some(rect)) = (self.drag_offset.get(), self.rect.get()) {
+                    let position = self.pos_on_axis(p) - offset - self.start(rect);
+                    self.set_position(max(position, 0) as u32);
+                }
+            },
+            Event::KeyDown(keycode, keymod) if self.common.focused.get() => {
+                let step = if ctrl_pressed(keymod) { 1 }else{ NUDGE_STEP };
+                match (self.direction, keycode) {
+                    (Direction::Horizon, Keycode::Left) | (Direction::Vertical, Keycode::Up) => {
+                        self.nudge(false, step);
+                    },
+                    (Direction::Horizon, Keycode::Right) | (Direction::Vertical, Keycode::Down) => {
+                        self.nudge(true, step);
+                    },
+                    (_, Keycode::Home) => {
+                        self.set_position(0);
+                    },
+                    (_, Keycode::End) => {
+                        self.set_position(::std::u32::MAX);
+                    },
+                    _ => (),
+                }
+            },
+            _ => (),
+        }
+        
+        for child in &[&self.first, &self.second] {
+            if let Some(w) = child.widget() {
+                w.event_handler(event);
+            }
+        }
+    }
+
+    fn size_on_axis(&self) -> SizeRange {
+        self.first.size(self.direction).sum(self.second.size(self.direction)).add(DIVIDER_SIZE)
+    }
+
+    fn size_across_axis(&self) -> SizeRange {
+        let mut size = SizeRange::default();
+        for child in &[&self.first, &self.second] {
+            if let Some(w) = child.widget() {
+                size = size.max(match self.direction {
+                    Direction::Horizon => w.preferred_height_for_width(w.preferred_width().natural),
+                    Direction::Vertical => w.preferred_width(),
+                });
+            }
+        }
+        size
+    }
+}
+
+macro_rules! impl_paned {
+    ($w:ident, $direction:expr) => {
+        impl $w {
+            pub fn new() -> Rc<$w> {
+                Rc::new($w{
+                    base: PanedBase::new($direction),
+                })
+            }
+
+            /// Set the first child.
+            /// If shrink is true, the child can be made smaller than its minimum size.
+            pub fn pack1<W: Into<AnyWidget>>(&self, w: W, shrink: bool) {
+                *self.base.first.widget.borrow_mut() = Some(w.into());
+                self.base.first.shrink.set(shrink);
+                self.base.layout_updated.set(false);
+                request_redraw();
+            }
+
+            /// Set the second child.
+            /// If shrink is true, the child can be made smaller than its minimum size.
+            pub fn pack2<W: Into<AnyWidget>>(&self, w: W, shrink: bool) {
+                *self.base.second.widget.borrow_mut() = Some(w.into());
+                self.base.second.shrink.set(shrink);
+                self.base.layout_updated.set(false);
+                request_redraw();
+            }
+
+            /// Set minimum sizes of each side. They are kept when the divider is moved.
+            pub fn set_min_sizes(&self, first: u32, second: u32) {
+                self.base.first.min_size.set(first);
+                self.base.second.min_size.set(second);
+                self.base.layout_updated.set(false);
+                request_redraw();
+            }
+
+            /// Set the size of the first child
+            pub fn set_position(&self, position: u32) {
+                self.base.set_position(position);
+            }
+
+            pub fn get_position(&self) -> u32 {
+                self.base.current_position()
+            }
+
+            /// Set a callback called with the new position when the divider is moved
+            pub fn on_position_changed<F>(&self, f: F) where F: Fn(u32) + 'static {
+                *self.base.callback_position_changed.borrow_mut() = Some(Rc::new(f));
+            }
+        }
+
+        impl WidgetTrait for $w {
+            fn common(&self) -> &WidgetCommon {
+                &self.base.common
+            }
+
+            fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
+                self.base.render(renderer)
+            }
+
+            fn alloc_rect(&self, rect: Rect) {
+                self.base.rect.set(Some(rect));
+                self.base.layout_updated.set(false);
+            }
+
+            fn event_handler(&self, event: &Event) {
+                self.base.event_handler(event);
+            }
+
+            fn cancel_input(&self) {
+                self.base.drag_offset.set(None);
+                for c in self.children() {
+                    c.cancel_input();
+                }
+            }
+
+            fn preferred_width(&self) -> SizeRange {
+                match self.base.direction {
+                    Direction::Horizon => self.base.size_on_axis(),
+                    Direction::Vertical => self.base.size_across_axis(),
+                }
+            }
+
+            fn preferred_height_for_width(&self, _width: u32) -> SizeRange {
+                match self.base.direction {
+                    Direction::Horizon => self.base.size_across_axis(),
+                    Direction::Vertical => self.base.size_on_axis(),
+                }
+            }
+        }
+
+        impl ContainerTrait for $w {
+            fn with_children<F: FnMut(&AnyWidget)>(&self, f: F) {
+                let mut f = f;
+
+                for child in &[&self.base.first, &self.base.second] {
+                    if let Some(ref w) = *child.widget.borrow() {
+                        f(w);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Container which has two children side by side with a draggable divider
+pub struct HPaned {
+    base: PanedBase,
+}
+
+/// Container which has two children one above the other with a draggable divider
+pub struct VPaned {
+    base: PanedBase,
+}
+
+impl_paned!(HPaned, Direction::Horizon);
+impl_paned!(VPaned, Direction::Vertical);
@@ -0,0 +1,330 @@
+use std::rc::{Rc, Weak};
+use std::cell::{RefCell, Cell};
+use std::cmp::max;
+use ::sdl2::mouse::Mouse;
+use ::sdl2::keyboard::Keycode;
+
+use prelude::*;
+use theme;
+use uicontext::{request_redraw, request_relayout};
+use widgets::rendering_insensitive;
+
+const INDICATOR_SIZE: u32 = 13;
+const SPACING: u32 = 4;
+
+/// RadioButtons sharing a group. At most one of them is selected.
+pub struct RadioGroup {
+    buttons: RefCell<Vec<Weak<RadioButton>>>,
+    selected: Cell<Option<usize>>,
+    callback_selection_changed: RefCell<Option<Rc<Fn(usize, &str) + 'static>>>,
+}
+
+impl RadioGroup {
+    pub fn new() -> Rc<RadioGroup> {
+        Rc::new(RadioGroup{
+            buttons: RefCell::new(Vec::new()),
+            selected: Cell::new(None),
+            callback_selection_changed: RefCell::new(None),
+        })
+    }
+
+    /// Index of the selected button. Buttons are numbered in the order they joined.
+    pub fn get_selected(&self) -> Option<usize> {
+        self.selected.get()
+    }
+
+    /// Select the button at the index.
+    /// The callback is called with the index and the button's value if the selection is changed.
+    pub fn set_selected(&self, index: usize) {
+        if self.selected.get() == Some(index) || index >= self.buttons.borrow().len() {
+            return;
+        }
+        self.selected.set(Some(index));
+        request_redraw();
+
+        let value = self.get_selected_value().unwrap_or_default();
+        let callback = self.callback_selection_changed.borrow().clone();
+        if let Some(f) = callback {
+            f(index, &value);
+        }
+    }
+
+    /// Value of the selected button
+    pub fn get_selected_value(&self) -> Option<String> {
+        self.selected.get().and_then(|i| self.get_button(i)).map(|b| b.get_value())
+    }
+
+    pub fn get_button(&self, index: usize) -> Option<Rc<RadioButton>> {
+        self.buttons.borrow().get(index).and_then(|b| b.upgrade())
+    }
+
+    pub fn n_buttons(&self) -> usize {
+        self.buttons.borrow().len()
+    }
+
+    /// Set a callback called with the index and the value of the newly selected button
+    pub fn on_selection_changed<F>(&self, f: F) where F: Fn(usize, &str) + 'static {
+        *self.callback_selection_changed.borrow_mut() = Some(Rc::new(f));
+    }
+
+    fn join(&self, button: &Rc<RadioButton>) -> usize {
+        let mut buttons = self.buttons.borrow_mut();
+        buttons.push(Rc::downgrade(button));
+        // The first button is selected at first
+        if self.selected.get().is_none() {
+            self.selected.set(Some(0));
+        }
+        buttons.len() - 1
+    }
+
+    /// The next button which can be selected by the keyboard.
+    /// Dropped, hidden and insensitive buttons are skipped.
+    fn neighbor(&self, index: usize, forward: bool) -> Option<usize> {
+        let n = self.buttons.borrow().len();
+        for i in 1..n {
+            let j = if forward { (index + i) % n }else{ (index + n - i) % n };
+            if let Some(b) = self.get_button(j) {
+                if b.get_visible() && b.get_sensitive() {
+                    return Some(j);
+                }
+            }
+        }
+        None
+    }
+
+    /// Select and focus a neighbor of the focused button.
+    /// The focus moves after the event is sent,
+    /// so the new button does not get the key which moved the focus.
+    fn move_focus(&self, index: usize, forward: bool) {
+        let target = match self.neighbor(index, forward) {
+            Some(t) => t,
+            None => { return; },
+        };
+        if let Some(b) = self.get_button(target) {
+            b.grab_focus();
+        }
+        self.set_selected(target);
+    }
+}
+
+/// A button selecting one of the options in a RadioGroup, with a round indicator and a label
+pub struct RadioButton {
+    common: WidgetCommon,
+    rect: Cell<Option<Rect>>,
+    child: RefCell<Option<AnyWidget>>,
+    group: Rc<RadioGroup>,
+    index: Cell<usize>,
+    value: RefCell<String>,
+    pressed: Cell<bool>,
+}
+
+impl RadioButton {
+    /// Create a button and add it to the group
+    pub fn new(group: &Rc<RadioGroup>) -> Rc<RadioButton> {
+        let button = Rc::new(RadioButton{
+            common: WidgetCommon::new(),
+            rect: Cell::new(None),
+            child: RefCell::new(None),
+            group: group.clone(),
+            index: Cell::new(0),
+            value: RefCell::new(String::new()),
+            pressed: Cell::new(false),
+        });
+        button.index.set(group.join(&button));
+        button
+    }
+
+    /// Create a button with a label. The text is also used as the value.
+    pub fn new_with_label(group: &Rc<RadioGroup>, s: &str) -> Rc<RadioButton> {
+        let button = RadioButton::new(group);
+        *button.child.borrow_mut() = Some(::widgets::Label::new(s).into());
+        *button.value.borrow_mut() = s.to_owned();
+        button
+    }
+
+    /// Set the widget shown on the right of the indicator
+    pub fn set_child<W: Into<AnyWidget>>(&self, w: W) {
+        *self.child.borrow_mut() = Some(w.into());
+        request_relayout();
+    }
+
+    /// Set the value reported to the group's callback
+    pub fn set_value(&self, value: &str) {
+        *self.value.borrow_mut() = value.to_owned();
+    }
+
+    pub fn get_value(&self) -> String {
+        self.value.borrow().clone()
+    }
+
+    pub fn get_group(&self) -> Rc<RadioGroup> {
+        self.group.clone()
+    }
+
+    /// Position in the group
+    pub fn get_index(&self) -> usize {
+        self.index.get()
+    }
+
+    /// Select this button in the group
+    pub fn set_active(&self) {
+        self.group.set_selected(self.index.get());
+    }
+
+    pub fn get_active(&self) -> bool {
+        self.group.selected.get() == Some(self.index.get())
+    }
+
+    fn indicator_rect(&self, rect: Rect) -> Rect {
+        let y = rect.y() + (rect.height() as i32 - INDICATOR_SIZE as i32) / 2;
+        Rect::new(rect.x(), y, INDICATOR_SIZE, INDICATOR_SIZE)
+    }
+
+    /// Draw a sunken circle, and a dot in it if active
+    fn render_indicator(&self, renderer: &mut Renderer, r: Rect) -> Result<(), String> {
+        let color = theme::get_theme_color();
+        let radius = (INDICATOR_SIZE / 2) as i32;
+        let (cx, cy) = (r.x() + radius, r.y() + radius);
+        // Half width of the circle at the height
+        let half_width = |dy: i32, radius: i32| ((radius * radius - dy * dy) as f64).sqrt().round() as i32;
+
+        renderer.set_draw_color(if self.pressed.get() { color.dark_background }else{ color.light_background });
+        for dy in -radius..(radius + 1) {
+            let dx = half_width(dy, radius);
+            try!(renderer.draw_line(Point::new(cx - dx, cy + dy), Point::new(cx + dx, cy + dy)));
+        }
+
+        // The upper left half of the edge is dark and the other is light
+        let mut edge = Vec::new();
+        for d in -radius..(radius + 1) {
+            let w = half_width(d, radius);
+            edge.extend_from_slice(&[(-w, d), (w, d), (d, -w), (d, w)]);
+        }
+        for &(dx, dy) in &edge {
+            renderer.set_draw_color(if dx + dy < 0 { color.dark_border }else{ color.light_border });
+            try!(renderer.draw_point(Point::new(cx + dx, cy + dy)));
+        }
+
+        if self.get_active() {
+            renderer.set_draw_color(if rendering_insensitive() {
+                color.insensitive_text
+            }else{
+                color.text
+            });
+            let dot_radius = radius / 2;
+            for dy in -dot_radius..(dot_radius + 1) {
+                let dx = half_width(dy, dot_radius);
+                try!(renderer.draw_line(Point::new(cx - dx, cy + dy), Point::new(cx + dx, cy + dy)));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl WidgetTrait for RadioButton {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
+    fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
+        let rect = self.rect.get().expect("No rectangle allocation at rendering");
+        try!(self.render_indicator(renderer, self.indicator_rect(rect)));
+
+        if let Some(ref c) = *self.child.borrow() {
+            try!(c.render(renderer));
+        }
+        Ok(())
+    }
+
+    fn alloc_rect(&self, rect: Rect) {
+        self.rect.set(Some(rect));
+
+        if let Some(ref c) = *self.child.borrow() {
+            let offset = INDICATOR_SIZE + SPACING;
+            let width = if rect.width() > offset { rect.width() - offset }else{ 0 };
+            c.alloc_rect(Rect::new(rect.x() + offset as i32, rect.y(), width, rect.height()));
+        }
+    }
+
+    fn event_handler(&self, event: &Event) {
+        let rect = match self.rect.get() {
+            Some(r) => r,
+            None => { return; },
+        };
+        let index = self.index.get();
+
+        match *event {
+            Event::ButtonDown(p, mouse_btn) => {
+                if rect.contains(p) {
+                    self.grab_focus();
+                    if mouse_btn == Mouse::Left {
+                        self.pressed.set(true);
+                        request_redraw();
+                    }
+                }
+            },
+            Event::ButtonUp(p, Mouse::Left) => {
+                if self.pressed.get() {
+                    self.pressed.set(false);
+                    request_redraw();
+                    // Releasing outside cancels the click
+                    if rect.contains(p) {
+                        self.set_active();
+                    }
+                }
+            },
+            Event::KeyDown(keycode, _) if self.has_focus() => {
+                match keycode {
+                    Keycode::Up | Keycode::Left => self.group.move_focus(index, false),
+                    Keycode::Down | Keycode::Right => self.group.move_focus(index, true),
+                    Keycode::Space => self.set_active(),
+                    _ => (),
+                }
+            },
+            Event::WindowFocusLost => {
+                self.cancel_input();
+            },
+            _ => (),
+        }
+    }
+
+    fn cancel_input(&self) {
+        if self.pressed.get() {
+            self.pressed.set(false);
+            request_redraw();
+        }
+        for c in self.children() {
+            c.cancel_input();
+        }
+    }
+
+    fn preferred_width(&self) -> SizeRange {
+        match self.with_child(|c| c.preferred_width()) {
+            Some(size) => size.add(INDICATOR_SIZE + SPACING),
+            None => SizeRange::fixed(INDICATOR_SIZE),
+        }
+    }
+
+    fn preferred_height_for_width(&self, width: u32) -> SizeRange {
+        let offset = INDICATOR_SIZE + SPACING;
+        let child_width = if width > offset { width - offset }else{ 0 };
+        let size = self.with_child(|c| c.preferred_height_for_width(child_width)).unwrap_or_default();
+        SizeRange::new(max(size.minimum, INDICATOR_SIZE), max(size.natural, INDICATOR_SIZE))
+    }
+}
+
+impl ContainerTrait for RadioButton {
+    fn with_children<F: FnMut(&AnyWidget)>(&self, f: F) {
+        let mut f = f;
+
+        if let Some(ref c) = *self.child.borrow() {
+            f(c);
+        }
+    }
+}
+
+impl OneChildContainerTrait for RadioButton {
+    fn with_child<F, R>(&self, f: F) -> Option<R> where F: FnOnce(&AnyWidget) -> R {
+        self.child.borrow().as_ref().map(f)
+    }
+}
@@ -0,0 +1,547 @@
+use std::cell::{RefCell, Cell};
+use std::rc::Rc;
+use std::cmp::{min, max};
+use ::sdl2::mouse::Mouse;
+use ::sdl2::keyboard::Keycode;
+
+use prelude::*;
+use theme;
+use enums::Direction;
+use uicontext::{request_redraw, request_relayout, request_focus, claim_wheel};
+use widgets::rendering_insensitive;
+
+const KNOB_LENGTH: u32 = 10;
+const KNOB_THICKNESS: u32 = 20;
+const TRACK_THICKNESS: u32 = 4;
+const TICK_LENGTH: u32 = 4;
+const VALUE_SPACING: u32 = 2;
+// Length of the slider which the natural size gives
+const NATURAL_LENGTH: u32 = 120;
+// Decimal places shown at most for steps without an exact decimal form
+const MAX_DIGITS: usize = 6;
+
+fn text_size(text: &str) -> (u32, u32) {
+    theme::get_default_font().size_of(text).unwrap_or((0, 0))
+}
+
+/// Decimal places needed to show multiples of the step exactly, e.g. 2 for 0.25
+fn step_digits(step: f64) -> usize {
+    if step <= 0.0 || !step.is_finite() {
+        return 2;
+    }
+    let mut digits = 0;
+    // Steps like 1/3 have no exact decimal form
+    while digits < MAX_DIGITS {
+        let scaled = step * 10f64.powi(digits as i32);
+        if (scaled - scaled.round()).abs() <= scaled * 1e-9 {
+            break;
+        }
+        digits += 1;
+    }
+    digits
+}
+
+/// Common implementation of HScale and VScale
+struct ScaleBase {
+    common: WidgetCommon,
+    direction: Direction,
+    rect: Cell<Option<Rect>>,
+    lower: Cell<f64>,
+    upper: Cell<f64>,
+    value: Cell<f64>,
+    step: Cell<f64>, // Values are snapped to multiples of step from lower. 0 means no snapping.
+    tick_interval: Cell<Option<f64>>,
+    draw_value: Cell<bool>,
+    drag_offset: Cell<Option<i32>>, // Distance between the pointer and the knob center while dragging
+    callback_value_changed: RefCell<Option<Rc<Fn(f64) + 'static>>>,
+    format_value: RefCell<Option<Box<Fn(f64) -> String + 'static>>>,
+}
+
+impl ScaleBase {
+    fn new(direction: Direction, lower: f64, upper: f64, step: f64) -> ScaleBase {
+        ScaleBase{
+            common: WidgetCommon::new(),
+            direction: direction,
+            rect: Cell::new(None),
+            lower: Cell::new(lower),
+            upper: Cell::new(upper),
+            value: Cell::new(lower),
+            step: Cell::new(if step > 0.0 { step }else{ 0.0 }),
+            tick_interval: Cell::new(None),
+            draw_value: Cell::new(true),
+            drag_offset: Cell::new(None),
+            callback_value_changed: RefCell::new(None),
+            format_value: RefCell::new(None),
+        }
+    }
+
+    fn range(&self) -> f64 {
+        self.upper.get() - self.lower.get()
+    }
+
+    /// Clamp the value into the range and snap it to the step
+    fn adjust(&self, value: f64) -> f64 {
+        let (lower, upper, step) = (self.lower.get(), self.upper.get(), self.step.get());
+        let value = value.min(upper).max(lower);
+        if step <= 0.0 {
+            return value;
+        }
+        // The upper end may not be on the steps
+        let snapped = lower + ((value - lower) / step).round() * step;
+        if snapped > upper { (snapped - step).max(lower) }else{ snapped }
+    }
+
+    fn set_value(&self, value: f64) {
+        let value = self.adjust(value);
+        if value == self.value.get() {
+            return;
+        }
+        self.value.set(value);
+        request_redraw();
+        let callback = self.callback_value_changed.borrow().clone();
+        if let Some(f) = callback {
+            f(value);
+        }
+    }
+
+    /// Amount moved by the arrow keys and the mouse wheel
+    fn small_step(&self) -> f64 {
+        if self.step.get() > 0.0 { self.step.get() }else{ self.range() / 100.0 }
+    }
+
+    /// Amount moved by Page Up and Page Down
+    fn page_step(&self) -> f64 {
+        self.small_step().max(self.range() / 10.0)
+    }
+
+    fn step_by(&self, amount: f64) {
+        let value = self.value.get();
+        self.set_value(value + amount);
+    }
+
+    fn format(&self, value: f64) -> String {
+        if let Some(ref f) = *self.format_value.borrow() {
+            return f(value);
+        }
+        format!("{:.*}", step_digits(self.step.get()), value)
+    }
+
+    /// Size of the value label along and across the slider.
+    /// The widest of the texts at the ends is used so that the size does not change while sliding.
+    fn value_label_size(&self) -> (u32, u32) {
+        if !self.draw_value.get() {
+            return (0, 0);
+        }
+        let (w1, h1) = text_size(&self.format(self.lower.get()));
+        let (w2, h2) = text_size(&self.format(self.upper.get()));
+        match self.direction {
+            Direction::Horizon => (max(w1, w2), max(h1, h2)),
+            Direction::Vertical => (max(h1, h2), max(w1, w2)),
+        }
+    }
+
+    /// Size across the slider of the part with the knob and the ticks
+    fn slider_thickness(&self) -> u32 {
+        KNOB_THICKNESS + if self.tick_interval.get().is_some() { TICK_LENGTH + 1 }else{ 0 }
+    }
+
+    fn length(&self, rect: Rect) -> u32 {
+        match self.direction {
+            Direction::Horizon => rect.width(),
+            Direction::Vertical => rect.height(),
+        }
+    }
+
+    fn pos_on_axis(&self, p: (i32, i32)) -> i32 {
+        match self.direction {
+            Direction::Horizon => p.0,
+            Direction::Vertical => p.1,
+        }
+    }
+
+    /// Make a rect from the position along the axis and the offset from the slider's side
+    fn axis_rect(&self, slider: Rect, start: i32, length: u32, offset: u32, thickness: u32) -> Rect {
+        match self.direction {
+            Direction::Horizon => Rect::new(start, slider.y() + offset as i32, length, thickness),
+            Direction::Vertical => Rect::new(slider.x() + offset as i32, start, thickness, length),
+        }
+    }
+
+    /// The part of the rect without the value label.
+    /// The label is above a horizontal slider and on the right of a vertical one.
+    fn slider_rect(&self, rect: Rect) -> Rect {
+        let label_thickness = match self.value_label_size().1 {
+            0 => 0,
+            t => t + VALUE_SPACING,
+        };
+        match self.direction {
+            Direction::Horizon => {
+                let label_thickness = min(label_thickness, rect.height());
+                Rect::new(rect.x(), rect.y() + label_thickness as i32,
+                          rect.width(), rect.height() - label_thickness)
+            },
+            Direction::Vertical => {
+                let label_thickness = min(label_thickness, rect.width());
+                Rect::new(rect.x(), rect.y(), rect.width() - label_thickness, rect.height())
+            },
+        }
+    }
+
+    /// The first position of the knob center and the distance it can move
+    fn travel(&self, slider: Rect) -> (i32, u32) {
+        let start = self.pos_on_axis((slider.x(), slider.y()));
+        let length = self.length(slider);
+        (start + (min(KNOB_LENGTH, length) / 2) as i32, length.saturating_sub(KNOB_LENGTH))
+    }
+
+    /// Position of the knob center for the value.
+    /// The lower end is at the left of a horizontal slider and at the bottom of a vertical one.
+    fn value_to_pos(&self, slider: Rect, value: f64) -> i32 {
+        let (start, travel) = self.travel(slider);
+        let ratio = if self.range() <= 0.0 { 0.0 }else{ (value - self.lower.get()) / self.range() };
+        let ratio = match self.direction {
+            Direction::Horizon => ratio,
+            Direction::Vertical => 1.0 - ratio,
+        };
+        start + (travel as f64 * ratio).round() as i32
+    }
+
+    fn pos_to_value(&self, slider: Rect, pos: i32) -> f64 {
+        let (start, travel) = self.travel(slider);
+        if travel == 0 {
+            return self.lower.get();
+        }
+        let ratio = (pos - start) as f64 / travel as f64;
+        let ratio = match self.direction {
+            Direction::Horizon => ratio,
+            Direction::Vertical => 1.0 - ratio,
+        };
+        self.lower.get() + ratio * self.range()
+    }
+
+    fn knob_rect(&self, slider: Rect) -> Rect {
+        let length = min(KNOB_LENGTH, self.length(slider));
+        let center = self.value_to_pos(slider, self.value.get());
+        self.axis_rect(slider, center - (length / 2) as i32, length, 0, KNOB_THICKNESS)
+    }
+
+    fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
+        let rect = self.rect.get().expect("No rectangle allocation at rendering");
+        let color = theme::get_theme_color();
+        let slider = self.slider_rect(rect);
+        let (start, travel) = self.travel(slider);
+
+        // Track
+        let track = self.axis_rect(
+            slider, start, travel + 1, (KNOB_THICKNESS - TRACK_THICKNESS) / 2, TRACK_THICKNESS);
+        renderer.set_draw_color(color.dark_background);
+        try!(renderer.fill_rect(track));
+        renderer.set_draw_color(color.dark_border);
+        try!(renderer.draw_rect(track));
+
+        // Ticks below or on the right of the knob
+        if let Some(interval) = self.tick_interval.get() {
+            let n_ticks = (self.range() / interval).floor();
+            // Skip ticks which would be packed into a solid bar
+            if interval > 0.0 && n_ticks <= (travel / 2) as f64 {
+                renderer.set_draw_color(color.dark_border);
+                for i in 0..(n_ticks as u32 + 1) {
+                    let pos = self.value_to_pos(slider, self.lower.get() + i as f64 * interval);
+                    try!(renderer.fill_rect(
+                        self.axis_rect(slider, pos, 1, KNOB_THICKNESS + 1, TICK_LENGTH)));
+                }
+            }
+        }
+
+        // Knob
+        let knob = self.knob_rect(slider);
+        let active = self.common.focused.get() || self.drag_offset.get().is_some();
+        renderer.set_draw_color(if active { color.light_background }else{ color.background });
+        try!(renderer.fill_rect(knob));
+        renderer.set_draw_color(color.dark_border);
+        try!(renderer.draw_rect(knob));
+        if knob.width() > 2 && knob.height() > 2 {
+            renderer.set_draw_color(color.light_border);
+            try!(renderer.draw_rect(Rect::new(
+                knob.x() + 1, knob.y() + 1, knob.width() - 2, knob.height() - 2)));
+        }
+
+        if self.draw_value.get() {
+            try!(self.render_value(renderer, rect, slider));
+        }
+        Ok(())
+    }
+
+    /// Draw the value next to the knob, kept inside the rect
+    fn render_value(&self, renderer: &mut Renderer, rect: Rect, slider: Rect) -> Result<(), String> {
+        let text = self.format(self.value.get());
+        if text.is_empty() {
+            return Ok(());
+        }
+        let (text_w, text_h) = text_size(&text);
+        let center = self.value_to_pos(slider, self.value.get());
+        let clamp = |pos: i32, length: u32, start: i32, end: i32| {
+            max(min(pos, end - length as i32), start)
+        };
+        let (x, y) = match self.direction {
+            Direction::Horizon => (
+                clamp(center - (text_w / 2) as i32, text_w, rect.x(), rect.right()),
+                rect.y()),
+            Direction::Vertical => (
+                slider.right() + VALUE_SPACING as i32,
+                clamp(center - (text_h / 2) as i32, text_h, rect.y(), rect.bottom())),
+        };
+
+        let text_color = if rendering_insensitive() {
+            theme::get_theme_color().insensitive_text
+        }else{
+            theme::get_text_color()
+        };
+        let font = theme::get_default_font();
+        let surface = try!(font.render(&text).blended(text_color).map_err(|e| e.to_string()));
+        let texture = try!(renderer.create_texture_from_surface(surface).map_err(|e| e.to_string()));
+        renderer.copy(&texture, None, Some(Rect::new(x, y, text_w, text_h)))
+    }
+
+    // Stop dragging the knob
+    fn cancel_input(&self) {
+        if self.drag_offset.get().is_some() {
+            self.drag_offset.set(None);
+            request_redraw();
+        }
+    }
+
+    fn event_handler(&self, event: &Event) {
+        let rect = match self.rect.get() {
+            Some(r) => r,
+            None => { return; },
+        };
+        let slider = self.slider_rect(rect);
+
+        match *event {
+            Event::ButtonDown(p, Mouse::Left) if slider.contains(p) => {
+                // Same as grab_focus of the scale widget
+                self.common.focus_requested.set(true);
+                request_focus();
+                let knob = self.knob_rect(slider);
+                let center = self.value_to_pos(slider, self.value.get());
+                if knob.contains(p) {
+                    self.drag_offset.set(Some(self.pos_on_axis(p) - center));
+                }else{
+                    // Jump to the clicked position and start dragging from there
+                    self.drag_offset.set(Some(0));
+                    self.set_value(self.pos_to_value(slider, self.pos_on_axis(p)));
+                }
+                request_redraw();
+            },
+            Event::MouseMotion(p) => {
+                if let Some(offset) = self.drag_offset.get() {
+                    self.set_value(self.pos_to_value(slider, self.pos_on_axis(p) - offset));
+                }
+            },
+            Event::ButtonUp(_, Mouse::Left) => {
+                if self.drag_offset.get().is_some() {
+                    self.drag_offset.set(None);
+                    request_redraw();
+                }
+            },
+            Event::WindowFocusLost => {
+                self.cancel_input();
+            },
+            Event::MouseWheel(p, (dx, dy)) if rect.contains(p) && claim_wheel() => {
+                let amount = if dy != 0 { dy }else{ dx };
+                self.step_by(amount as f64 * self.small_step());
+            },
+            Event::KeyDown(keycode, _) if self.common.focused.get() => {
+                match keycode {
+                    Keycode::Left | Keycode::Down => self.step_by(-self.small_step()),
+                    Keycode::Right | Keycode::Up => self.step_by(self.small_step()),
+                    Keycode::PageDown => self.step_by(-self.page_step()),
+                    Keycode::PageUp => self.step_by(self.page_step()),
+                    Keycode::Home => {
+                        let lower = self.lower.get();
+                        self.set_value(lower);
+                    },
+                    Keycode::End => {
+                        let upper = self.upper.get();
+                        self.set_value(upper);
+                    },
+                    _ => (),
+                }
+            },
+            _ => (),
+        }
+    }
+
+    fn size_on_axis(&self) -> SizeRange {
+        let label_length = self.value_label_size().0;
+        let minimum = max(KNOB_LENGTH * 3, label_length);
+        SizeRange::new(minimum, max(minimum, NATURAL_LENGTH))
+    }
+
+    fn size_across_axis(&self) -> SizeRange {
+        let label_thickness = match self.value_label_size().1 {
+            0 => 0,
+            t => t + VALUE_SPACING,
+        };
+        SizeRange::fixed(self.slider_thickness() + label_thickness)
+    }
+}
+
+macro_rules! impl_scale {
+    ($w:ident, $direction:expr) => {
+        impl $w {
+            /// Create a scale from 0 to 100 with step 1
+            pub fn new() -> Rc<$w> {
+                $w::new_with_range(0.0, 100.0, 1.0)
+            }
+
+            /// Create a scale whose value is snapped to multiples of step from lower.
+            /// If step is 0, the value is not snapped.
+            pub fn new_with_range(lower: f64, upper: f64, step: f64) -> Rc<$w> {
+                Rc::new($w{
+                    base: ScaleBase::new($direction, lower, upper, step),
+                })
+            }
+
+            /// Set the snapping step, which is also moved by the arrow keys and the mouse wheel
+            pub fn set_step(&self, step: f64) {
+                self.base.step.set(if step > 0.0 { step }else{ 0.0 });
+                let value = self.base.value.get();
+                self.base.set_value(value);
+                request_redraw();
+            }
+
+            pub fn get_step(&self) -> f64 {
+                self.base.step.get()
+            }
+
+            /// Draw tick marks at every interval from lower. None removes the marks.
+            pub fn set_tick_interval(&self, interval: Option<f64>) {
+                self.base.tick_interval.set(interval.filter(|i| *i > 0.0));
+                request_relayout();
+            }
+
+            pub fn get_tick_interval(&self) -> Option<f64> {
+                self.base.tick_interval.get()
+            }
+
+            /// Show the current value next to the knob
+            pub fn set_draw_value(&self, draw_value: bool) {
+                self.base.draw_value.set(draw_value);
+                request_relayout();
+            }
+
+            pub fn get_draw_value(&self) -> bool {
+                self.base.draw_value.get()
+            }
+
+            /// Set a function making the text of the shown value
+            pub fn set_format_value<F>(&self, f: F) where F: Fn(f64) -> String + 'static {
+                *self.base.format_value.borrow_mut() = Some(Box::new(f));
+                request_relayout();
+            }
+        }
+
+        impl WidgetTrait for $w {
+            fn common(&self) -> &WidgetCommon {
+                &self.base.common
+            }
+
+            fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
+                self.base.render(renderer)
+            }
+
+            fn alloc_rect(&self, rect: Rect) {
+                self.base.rect.set(Some(rect));
+            }
+
+            fn event_handler(&self, event: &Event) {
+                self.base.event_handler(event);
+            }
+
+            fn cancel_input(&self) {
+                self.base.cancel_input();
+            }
+
+            fn preferred_width(&self) -> SizeRange {
+                match self.base.direction {
+                    Direction::Horizon => self.base.size_on_axis(),
+                    Direction::Vertical => self.base.size_across_axis(),
+                }
+            }
+
+            fn preferred_height_for_width(&self, _width: u32) -> SizeRange {
+                match self.base.direction {
+                    Direction::Horizon => self.base.size_across_axis(),
+                    Direction::Vertical => self.base.size_on_axis(),
+                }
+            }
+        }
+
+        impl RangeTrait for $w {
+            fn set_lower(&self, lower: f64) {
+                self.base.lower.set(lower);
+                let value = self.base.value.get();
+                self.base.set_value(value);
+                request_relayout();
+            }
+            fn get_lower(&self) -> f64 {
+                self.base.lower.get()
+            }
+            fn set_upper(&self, upper: f64) {
+                self.base.upper.set(upper);
+                let value = self.base.value.get();
+                self.base.set_value(value);
+                request_relayout();
+            }
+            fn get_upper(&self) -> f64 {
+                self.base.upper.get()
+            }
+            fn set_value(&self, value: f64) {
+                self.base.set_value(value);
+            }
+            fn get_value(&self) -> f64 {
+                self.base.value.get()
+            }
+            fn step_dec_value(&self) {
+                let step = self.base.small_step();
+                self.base.step_by(-step);
+            }
+            fn step_inc_value(&self) {
+                let step = self.base.small_step();
+                self.base.step_by(step);
+            }
+            fn on_value_changed<F>(&self, f: F) where F: Fn(f64) + 'static {
+                *self.base.callback_value_changed.borrow_mut() = Some(Rc::new(f));
+            }
+        }
+    }
+}
+
+/// Horizontal slider with a draggable knob. The value increases to the right.
+pub struct HScale {
+    base: ScaleBase,
+}
+
+/// Vertical slider with a draggable knob. The value increases upward.
+pub struct VScale {
+    base: ScaleBase,
+}
+
+impl_scale!(HScale, Direction::Horizon);
+impl_scale!(VScale, Direction::Vertical);
+
+#[cfg(test)]
+mod tests {
+    use super::{step_digits, MAX_DIGITS};
+
+    #[test]
+    fn digits_of_steps() {
+        assert_eq!(step_digits(0.25), 2);
+        assert_eq!(step_digits(1.0), 0);
+        assert_eq!(step_digits(0.1), 1);
+        assert_eq!(step_digits(1.0 / 3.0), MAX_DIGITS);
+        assert_eq!(step_digits(0.0), 2);
+    }
+}
@@ -1,155 +1,435 @@
 
 use std::cell::{RefCell, Cell};
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
+use std::cmp::{min, max};
+use std::time::Duration;
 use ::sdl2::mouse::Mouse;
 
 use prelude::*;
 use theme;
-use uicontext::request_redraw;
+use enums::Direction;
+use uicontext::{request_redraw, add_timeout, claim_wheel};
+
+const MIN_THUMB_SIZE: u32 = 8;
+// Holding an arrow steps the value after REPEAT_DELAY every REPEAT_INTERVAL
+const REPEAT_DELAY: u64 = 400;
+const REPEAT_INTERVAL: u64 = 50;
 
 fn get_bar_size() -> u32 {
     theme::get_theme_layout().size_scrollbar
 }
 
-macro_rules! impl_range {
-    ($w:ty) => {
-        impl RangeTrait for $w {
-            fn set_lower(&self, lower: f64) {
-                self.lower.set(lower);
-            }
-            fn get_lower(&self) -> f64 {
-                self.lower.get()
-            }
-            fn set_upper(&self, upper: f64) {
-                self.upper.set(upper);
-            }
-            fn get_upper(&self) -> f64 {
-                self.upper.get()
-            }
-            fn set_value(&self, value: f64) {
-                let value = if value < self.lower.get() {
-                    self.lower.get()
-                }else if value > self.upper.get() {
-                    self.upper.get()
-                }else{
-                    value
-                };
-                    
-                self.value.set(value);
-                if let Some(ref f) = *self.callback_value_changed.borrow() {
-                    f(value);
-                }
-            }
-            fn get_value(&self) -> f64 {
-                self.value.get()
-            }
-            fn step_dec_value(&self) {
-                self.set_value(self.value.get() - self.step.get());
-            }
-            fn step_inc_value(&self) {
-                self.set_value(self.value.get() + self.step.get());
-            }
-            fn on_value_changed<F>(&self, f: F) where F: Fn(f64) + 'static {
-                *self.callback_value_changed.borrow_mut() = Some(Box::new(f));
-            }
-        }
-    }
-}
-
-pub struct HScrollBar {
+/// Common implementation of HScrollBar and VScrollBar
+struct ScrollBarBase {
+    common: WidgetCommon,
+    direction: Direction,
     rect: Cell<Option<Rect>>,
-    left_arrow_box: Cell<Option<Rect>>,
-    right_arrow_box: Cell<Option<Rect>>,
-
-    // Members for RangeTrait
     lower: Cell<f64>,
     upper: Cell<f64>,
     value: Cell<f64>,
     step: Cell<f64>,
-    callback_value_changed: RefCell<Option<Box<Fn(f64) + 'static>>>,
+    page_size: Cell<f64>,
+    drag_offset: Cell<Option<i32>>, // Distance between the pointer and the thumb start while dragging
+    // The held arrow (-1 or 1) and a counter telling repeats of older presses to stop
+    pressed_arrow: Cell<Option<i32>>,
+    press_count: Cell<u32>,
+    callback_value_changed: RefCell<Option<Rc<Fn(f64) + 'static>>>,
 }
 
-impl HScrollBar {
-    pub fn new() -> Rc<HScrollBar> {
-        Rc::new(HScrollBar{
+impl ScrollBarBase {
+    fn new(direction: Direction) -> ScrollBarBase {
+        ScrollBarBase{
+            common: WidgetCommon::new(),
+            direction: direction,
             rect: Cell::new(None),
-            left_arrow_box: Cell::new(None),
-            right_arrow_box: Cell::new(None),
             lower: Cell::new(0.0), upper: Cell::new(1.0), value: Cell::new(0.0), step: Cell::new(0.1),
+            page_size: Cell::new(0.0),
+            drag_offset: Cell::new(None),
+            pressed_arrow: Cell::new(None),
+            press_count: Cell::new(0),
             callback_value_changed: RefCell::new(None),
-        })
+        }
+    }
+
+    fn length(&self, rect: Rect) -> u32 {
+        match self.direction {
+            Direction::Horizon => rect.width(),
+            Direction::Vertical => rect.height(),
+        }
+    }
+
+    fn thickness(&self, rect: Rect) -> u32 {
+        match self.direction {
+            Direction::Horizon => rect.height(),
+            Direction::Vertical => rect.width(),
+        }
+    }
+
+    fn pos_on_axis(&self, p: (i32, i32)) -> i32 {
+        match self.direction {
+            Direction::Horizon => p.0,
+            Direction::Vertical => p.1,
+        }
+    }
+
+    /// Cut a part of the rect along the bar
+    fn sub_rect(&self, rect: Rect, offset: u32, length: u32) -> Rect {
+        match self.direction {
+            Direction::Horizon => Rect::new(rect.x() + offset as i32, rect.y(), length, rect.height()),
+            Direction::Vertical => Rect::new(rect.x(), rect.y() + offset as i32, rect.width(), length),
+        }
+    }
+
+    /// Size of the arrow boxes at each end. They are squares unless the bar is too short.
+    fn arrow_size(&self, rect: Rect) -> u32 {
+        min(self.thickness(rect), self.length(rect) / 2)
+    }
+
+    fn dec_arrow_rect(&self, rect: Rect) -> Rect {
+        self.sub_rect(rect, 0, self.arrow_size(rect))
+    }
+
+    fn inc_arrow_rect(&self, rect: Rect) -> Rect {
+        let size = self.arrow_size(rect);
+        self.sub_rect(rect, self.length(rect) - size, size)
+    }
+
+    fn trough_rect(&self, rect: Rect) -> Rect {
+        let size = self.arrow_size(rect);
+        self.sub_rect(rect, size, self.length(rect) - size * 2)
+    }
+
+    /// The largest value. The page at the value must fit in the range.
+    fn max_value(&self) -> f64 {
+        let upper = self.upper.get() - self.page_size.get();
+        if upper > self.lower.get() { upper }else{ self.lower.get() }
+    }
+
+    /// Offset from the trough start and length of the thumb
+    fn thumb_geometry(&self, trough_length: u32) -> (u32, u32) {
+        let range = self.upper.get() - self.lower.get();
+        let page = self.page_size.get();
+        let thumb_length = if range <= 0.0 || page <= 0.0 {
+            MIN_THUMB_SIZE
+        }else{
+            max((trough_length as f64 * (page / range).min(1.0)) as u32, MIN_THUMB_SIZE)
+        };
+        let thumb_length = min(thumb_length, trough_length);
+
+        let scrollable = self.max_value() - self.lower.get();
+        let space = (trough_length - thumb_length) as f64;
+        let offset = if scrollable <= 0.0 { 0.0 }else{
+            space * (self.value.get() - self.lower.get()) / scrollable
+        };
+        (offset.round() as u32, thumb_length)
+    }
+
+    fn thumb_rect(&self, rect: Rect) -> Rect {
+        let trough = self.trough_rect(rect);
+        let (offset, length) = self.thumb_geometry(self.length(trough));
+        self.sub_rect(trough, offset, length)
+    }
+
+    fn set_value(&self, value: f64) {
+        let value = if value < self.lower.get() {
+            self.lower.get()
+        }else if value > self.max_value() {
+            self.max_value()
+        }else{
+            value
+        };
+
+        if value == self.value.get() {
+            return;
+        }
+        self.value.set(value);
+        request_redraw();
+        let callback = self.callback_value_changed.borrow().clone();
+        if let Some(f) = callback {
+            f(value);
+        }
+    }
+
+    /// Amount moved by a click on the trough
+    fn page_step(&self) -> f64 {
+        if self.page_size.get() > 0.0 { self.page_size.get() }else{ self.step.get() }
+    }
+
+    /// Move the thumb so that its start is at the given position on the axis
+    fn drag_to(&self, thumb_start: i32) {
+        let rect = match self.rect.get() {
+            Some(r) => r,
+            None => { return; },
+        };
+        let trough = self.trough_rect(rect);
+        let trough_length = self.length(trough);
+        let (_, thumb_length) = self.thumb_geometry(trough_length);
+        let space = trough_length - thumb_length;
+        if space == 0 {
+            return;
+        }
+
+        let offset = thumb_start - self.pos_on_axis((trough.x(), trough.y()));
+        let ratio = offset as f64 / space as f64;
+        self.set_value(self.lower.get() + ratio * (self.max_value() - self.lower.get()));
     }
-}
 
-impl WidgetTrait for HScrollBar {
     fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
         let rect = self.rect.get().expect("No rectangle allocation at rendering");
-        let left_arrow_box = self.left_arrow_box.get().unwrap();
-        let right_arrow_box = self.right_arrow_box.get().unwrap();
         let color = theme::get_theme_color();
 
-        
-        let bar_size = get_bar_size();
-
-        let top_left_corner = (rect.x(), rect.y());
         renderer.set_draw_color(color.dark_background);
-        try!(renderer.fill_rect(Rect::new(
-            top_left_corner.0, top_left_corner.1, rect.width(), bar_size)));
+        try!(renderer.fill_rect(rect));
         renderer.set_draw_color(color.dark_border);
-        try!(renderer.draw_rect(Rect::new(
-            top_left_corner.0, top_left_corner.1, rect.width(), bar_size)));
-        
-        renderer.set_draw_color(color.background);
-        try!(renderer.fill_rect(left_arrow_box));
-        renderer.set_draw_color(color.dark_border);
-        try!(renderer.draw_rect(left_arrow_box));
+        try!(renderer.draw_rect(rect));
+
+        let pressed = self.pressed_arrow.get();
+        try!(self.render_arrow(renderer, self.dec_arrow_rect(rect), -1, pressed == Some(-1)));
+        try!(self.render_arrow(renderer, self.inc_arrow_rect(rect), 1, pressed == Some(1)));
 
-        renderer.set_draw_color(color.background);
-        try!(renderer.fill_rect(right_arrow_box));
+        let thumb = self.thumb_rect(rect);
+        renderer.set_draw_color(if self.drag_offset.get().is_some() { color.light_background }else{ color.background });
+        try!(renderer.fill_rect(thumb));
         renderer.set_draw_color(color.dark_border);
-        try!(renderer.draw_rect(right_arrow_box));
-        
-        
-        
+        try!(renderer.draw_rect(thumb));
+        if thumb.width() > 2 && thumb.height() > 2 {
+            renderer.set_draw_color(color.light_border);
+            try!(renderer.draw_rect(Rect::new(
+                thumb.x() + 1, thumb.y() + 1, thumb.width() - 2, thumb.height() - 2)));
+        }
         Ok(())
     }
 
-    fn alloc_rect(&self, rect: Rect) {
-        self.rect.set(Some(rect));
+    /// Draw an arrow box. `dir` is -1 for the left or up arrow and 1 for the other.
+    fn render_arrow(&self, renderer: &mut Renderer, rect: Rect, dir: i32, pressed: bool)
+                    -> Result<(), String> {
+        let color = theme::get_theme_color();
+        renderer.set_draw_color(if pressed { color.dark_background }else{ color.background });
+        try!(renderer.fill_rect(rect));
+        renderer.set_draw_color(color.dark_border);
+        try!(renderer.draw_rect(rect));
+
+        // Draw a filled triangle line by line
+        let size = min(rect.width(), rect.height()) as i32 / 2;
+        let cx = rect.x() + rect.width() as i32 / 2;
+        let cy = rect.y() + rect.height() as i32 / 2;
+        renderer.set_draw_color(theme::get_text_color());
+        for i in 0..(size / 2 + 1) {
+            // The i-th line is i pixels away from the tip and 2i pixels long
+            let offset = -dir * (i - size / 4);
+            try!(match self.direction {
+                Direction::Horizon => renderer.draw_line(
+                    Point::new(cx + offset, cy - i), Point::new(cx + offset, cy + i)),
+                Direction::Vertical => renderer.draw_line(
+                    Point::new(cx - i, cy + offset), Point::new(cx + i, cy + offset)),
+            });
+        }
+        Ok(())
+    }
 
-        let bar_size = get_bar_size();
-        let top_left_corner = (rect.x(), rect.y());
-        let top_right_corner = (rect.x() + rect.width() as i32, rect.y());
-        
-        self.left_arrow_box.set(Some(Rect::new(
-            top_left_corner.0, top_left_corner.1 + 1, bar_size - 2, bar_size - 2)));
-        self.right_arrow_box.set(Some(Rect::new(
-            top_right_corner.0 - bar_size as i32 + 2, top_right_corner.1 + 1, bar_size - 2, bar_size - 2)));
+    // Stop dragging the thumb and repeating the pressed arrow
+    fn cancel_input(&self) {
+        if self.drag_offset.get().is_some() || self.pressed_arrow.get().is_some() {
+            self.drag_offset.set(None);
+            self.pressed_arrow.set(None);
+            request_redraw();
+        }
     }
 
-    fn event_handler(&self, event: &Event) {
-        let rect_left_arrow_box = self.left_arrow_box.get().unwrap();
-        let rect_right_arrow_box = self.right_arrow_box.get().unwrap();
-        
+    fn event_handler(&self, event: &Event, weak: Weak<ScrollBarBase>) {
+        let rect = match self.rect.get() {
+            Some(r) => r,
+            None => { return; },
+        };
+
         match *event {
-            Event::ButtonDown((x, y), mouse_btn) => {
-                if mouse_btn == Mouse::Left && self.rect.get().unwrap().contains((x, y)) {
-                    if rect_left_arrow_box.contains((x, y)) { // Decreases value
-                        self.step_dec_value();
-                        request_redraw();
-                    }else if rect_right_arrow_box.contains((x, y)) { // Increases value
-                        self.step_inc_value();
-                        request_redraw();
-                    }
+            Event::ButtonDown(p, Mouse::Left) if rect.contains(p) => {
+                let thumb = self.thumb_rect(rect);
+                if self.dec_arrow_rect(rect).contains(p) {
+                    self.press_arrow(-1, weak);
+                }else if self.inc_arrow_rect(rect).contains(p) {
+                    self.press_arrow(1, weak);
+                }else if thumb.contains(p) {
+                    self.drag_offset.set(Some(self.pos_on_axis(p) - self.pos_on_axis((thumb.x(), thumb.y()))));
+                    request_redraw();
+                }else if self.pos_on_axis(p) < self.pos_on_axis((thumb.x(), thumb.y())) {
+                    self.set_value(self.value.get() - self.page_step());
+                }else{
+                    self.set_value(self.value.get() + self.page_step());
+                }
+            },
+            Event::MouseMotion(p) => {
+                // The drag continues while the pointer is outside of the bar
+                if let Some(offset) = self.drag_offset.get() {
+                    self.drag_to(self.pos_on_axis(p) - offset);
                 }
             },
-            _ => {},
+            Event::ButtonUp(_, Mouse::Left) | Event::WindowFocusLost => {
+                self.cancel_input();
+            },
+            Event::MouseWheel(p, (dx, dy)) if rect.contains(p) && claim_wheel() => {
+                let amount = match self.direction {
+                    Direction::Horizon => if dx != 0 { dx }else{ -dy },
+                    Direction::Vertical => -dy,
+                };
+                self.set_value(self.value.get() + amount as f64 * self.step.get());
+            },
+            _ => (),
         }
     }
 
-    fn requested_size(&self) -> Option<(u32, u32)> {
-        Some((get_bar_size() * 2, get_bar_size()))
+    /// Step the value and keep stepping while the arrow is held
+    fn press_arrow(&self, dir: i32, weak: Weak<ScrollBarBase>) {
+        let count = self.press_count.get().wrapping_add(1);
+        self.press_count.set(count);
+        self.pressed_arrow.set(Some(dir));
+        self.step_by(dir);
+        schedule_repeat(weak, count, REPEAT_DELAY);
+    }
+
+    fn step_by(&self, dir: i32) {
+        self.set_value(self.value.get() + dir as f64 * self.step.get());
+        request_redraw();
+    }
+}
+
+fn schedule_repeat(weak: Weak<ScrollBarBase>, count: u32, delay: u64) {
+    add_timeout(Duration::from_millis(delay), move || {
+        if let Some(base) = weak.upgrade() {
+            if let Some(dir) = base.pressed_arrow.get() {
+                if base.press_count.get() == count {
+                    base.step_by(dir);
+                    schedule_repeat(Rc::downgrade(&base), count, REPEAT_INTERVAL);
+                }
+            }
+        }
+    });
+}
+
+macro_rules! impl_scrollbar {
+    ($w:ident, $direction:expr) => {
+        impl $w {
+            pub fn new() -> Rc<$w> {
+                Rc::new($w{
+                    base: Rc::new(ScrollBarBase::new($direction)),
+                })
+            }
+
+            /// Set the size of the visible part, which decides the length of the thumb.
+            /// The value can be up to upper - page_size.
+            pub fn set_page_size(&self, page_size: f64) {
+                self.base.page_size.set(if page_size > 0.0 { page_size }else{ 0.0 });
+                let value = self.base.value.get();
+                self.base.set_value(value);
+                request_redraw();
+            }
+
+            pub fn get_page_size(&self) -> f64 {
+                self.base.page_size.get()
+            }
+
+            /// Set the range, the page size and the value at once
+            pub fn configure(&self, lower: f64, upper: f64, page_size: f64, value: f64) {
+                self.base.lower.set(lower);
+                self.base.upper.set(upper);
+                self.base.page_size.set(if page_size > 0.0 { page_size }else{ 0.0 });
+                self.base.set_value(value);
+                request_redraw();
+            }
+
+            /// Set the amount moved by the arrows and the mouse wheel
+            pub fn set_step(&self, step: f64) {
+                self.base.step.set(step);
+            }
+
+            pub fn get_step(&self) -> f64 {
+                self.base.step.get()
+            }
+        }
+
+        impl WidgetTrait for $w {
+            fn common(&self) -> &WidgetCommon {
+                &self.base.common
+            }
+
+            fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
+                self.base.render(renderer)
+            }
+
+            fn alloc_rect(&self, rect: Rect) {
+                self.base.rect.set(Some(rect));
+            }
+
+            fn event_handler(&self, event: &Event) {
+                self.base.event_handler(event, Rc::downgrade(&self.base));
+            }
+
+            fn cancel_input(&self) {
+                self.base.cancel_input();
+            }
+
+            fn preferred_width(&self) -> SizeRange {
+                match self.base.direction {
+                    Direction::Horizon => SizeRange::fixed(get_bar_size() * 2),
+                    Direction::Vertical => SizeRange::fixed(get_bar_size()),
+                }
+            }
+
+            fn preferred_height_for_width(&self, _width: u32) -> SizeRange {
+                match self.base.direction {
+                    Direction::Horizon => SizeRange::fixed(get_bar_size()),
+                    Direction::Vertical => SizeRange::fixed(get_bar_size() * 2),
+                }
+            }
+        }
+
+        impl RangeTrait for $w {
+            fn set_lower(&self, lower: f64) {
+                self.base.lower.set(lower);
+                let value = self.base.value.get();
+                self.base.set_value(value);
+                request_redraw();
+            }
+            fn get_lower(&self) -> f64 {
+                self.base.lower.get()
+            }
+            fn set_upper(&self, upper: f64) {
+                self.base.upper.set(upper);
+                let value = self.base.value.get();
+                self.base.set_value(value);
+                request_redraw();
+            }
+            fn get_upper(&self) -> f64 {
+                self.base.upper.get()
+            }
+            fn set_value(&self, value: f64) {
+                self.base.set_value(value);
+            }
+            fn get_value(&self) -> f64 {
+                self.base.value.get()
+            }
+            fn step_dec_value(&self) {
+                self.base.step_by(-1);
+            }
+            fn step_inc_value(&self) {
+                self.base.step_by(1);
+            }
+            fn on_value_changed<F>(&self, f: F) where F: Fn(f64) + 'static {
+                *self.base.callback_value_changed.borrow_mut() = Some(Rc::new(f));
+            }
+        }
     }
 }
 
-impl_range!(HScrollBar);
+/// Horizontal scrollbar with a draggable thumb
+pub struct HScrollBar {
+    base: Rc<ScrollBarBase>,
+}
+
+/// Vertical scrollbar with a draggable thumb
+pub struct VScrollBar {
+    base: Rc<ScrollBarBase>,
+}
+
+impl_scrollbar!(HScrollBar, Direction::Horizon);
+impl_scrollbar!(VScrollBar, Direction::Vertical);
@@ -0,0 +1,350 @@
+
+use std::rc::{Rc, Weak};
+use std::cell::{RefCell, Cell};
+use std::cmp::{min, max};
+use ::sdl2::keyboard::Keycode;
+
+use prelude::*;
+use theme;
+use enums::ScrollPolicy;
+use uicontext::{request_redraw, request_relayout, claim_wheel};
+use widgets::{HScrollBar, VScrollBar};
+
+const SCROLL_STEP: i32 = 20;
+
+fn get_bar_size() -> u32 {
+    theme::get_theme_layout().size_scrollbar
+}
+
+/// Sizes and positions calculated from the allocated rect and the child
+#[derive(Clone, Copy)]
+struct Geometry {
+    viewport: Rect,
+    child_size: (u32, u32),
+    hbar: Option<Rect>,
+    vbar: Option<Rect>,
+}
+
+/// Container which shows a part of its child and can be scrolled
+pub struct ScrolledWindow {
+    common: WidgetCommon,
+    rect: Cell<Option<Rect>>,
+    child: RefCell<Option<AnyWidget>>,
+    hpolicy: Cell<ScrollPolicy>,
+    vpolicy: Cell<ScrollPolicy>,
+    offset: Cell<(u32, u32)>,
+    geometry: Cell<Option<Geometry>>,
+    hbar: Rc<HScrollBar>,
+    vbar: Rc<VScrollBar>,
+}
+
+impl ScrolledWindow {
+    pub fn new() -> Rc<ScrolledWindow> {
+        let window = Rc::new(ScrolledWindow{
+            common: WidgetCommon::new(),
+            rect: Cell::new(None),
+            child: RefCell::new(None),
+            hpolicy: Cell::new(ScrollPolicy::Automatic),
+            vpolicy: Cell::new(ScrollPolicy::Automatic),
+            offset: Cell::new((0, 0)),
+            geometry: Cell::new(None),
+            hbar: HScrollBar::new(),
+            vbar: VScrollBar::new(),
+        });
+        window.hbar.set_step(SCROLL_STEP as f64);
+        window.vbar.set_step(SCROLL_STEP as f64);
+
+        let weak: Weak<ScrolledWindow> = Rc::downgrade(&window);
+        window.hbar.on_value_changed(move |value| {
+            if let Some(w) = weak.upgrade() {
+                w.scroll_to(value.round() as u32, w.offset.get().1);
+            }
+        });
+        let weak: Weak<ScrolledWindow> = Rc::downgrade(&window);
+        window.vbar.on_value_changed(move |value| {
+            if let Some(w) = weak.upgrade() {
+                w.scroll_to(w.offset.get().0, value.round() as u32);
+            }
+        });
+        window
+    }
+
+    pub fn set_child<W: Into<AnyWidget>>(&self, w: W) {
+        *self.child.borrow_mut() = Some(w.into());
+        self.geometry.set(None);
+        request_relayout();
+    }
+
+    /// Set when horizontal and vertical scrollbars are shown
+    pub fn set_policy(&self, hpolicy: ScrollPolicy, vpolicy: ScrollPolicy) {
+        self.hpolicy.set(hpolicy);
+        self.vpolicy.set(vpolicy);
+        self.geometry.set(None);
+        request_relayout();
+    }
+
+    pub fn get_policy(&self) -> (ScrollPolicy, ScrollPolicy) {
+        (self.hpolicy.get(), self.vpolicy.get())
+    }
+
+    /// Scroll to the given position of the child
+    pub fn scroll_to(&self, x: u32, y: u32) {
+        let (x, y) = match self.geometry.get() {
+            Some(g) => {
+                let max_x = g.child_size.0.saturating_sub(g.viewport.width());
+                let max_y = g.child_size.1.saturating_sub(g.viewport.height());
+                (min(x, max_x), min(y, max_y))
+            },
+            None => (x, y),
+        };
+        if self.offset.get() != (x, y) {
+            self.offset.set((x, y));
+            self.alloc_child();
+            request_redraw();
+        }
+    }
+
+    /// Return the position of the child shown at the top left corner
+    pub fn get_scroll_offset(&self) -> (u32, u32) {
+        self.offset.get()
+    }
+
+    fn scroll_by(&self, dx: i32, dy: i32) {
+        let (x, y) = self.offset.get();
+        self.scroll_to(max(x as i32 + dx, 0) as u32, max(y as i32 + dy, 0) as u32);
+    }
+
+    fn calc_geometry(&self, rect: Rect) -> Geometry {
+        let (child_width, child_height) = match *self.child.borrow() {
+            Some(ref c) => {
+                let width = c.preferred_width().natural;
+                (width, c.preferred_height_for_width(width).natural)
+            },
+            None => (0, 0),
+        };
+        let bar_size = get_bar_size();
+        let hpolicy = self.hpolicy.get();
+        let vpolicy = self.vpolicy.get();
+        let mut show_hbar = hpolicy == ScrollPolicy::Always;
+        let mut show_vbar = vpolicy == ScrollPolicy::Always;
+        let mut view_size = (0, 0);
+        let mut child_size = (0, 0);
+
+        // Showing a scrollbar narrows the viewport and may require the other scrollbar
+        for _ in 0..3 {
+            let view_width = rect.width().saturating_sub(if show_vbar { bar_size }else{ 0 });
+            let view_height = rect.height().saturating_sub(if show_hbar { bar_size }else{ 0 });
+            let width = if hpolicy == ScrollPolicy::Never { view_width }else{ max(child_width, view_width) };
+            let height = match *self.child.borrow() {
+                Some(ref c) if width != child_width => c.preferred_height_for_width(width).natural,
+                _ => child_height,
+            };
+            let height = if vpolicy == ScrollPolicy::Never { view_height }else{ max(height, view_height) };
+
+            view_size = (view_width, view_height);
+            child_size = (width, height);
+            show_hbar |= hpolicy == ScrollPolicy::Automatic && width > view_width;
+            show_vbar |= vpolicy == ScrollPolicy::Automatic && height > view_height;
+        }
+
+        Geometry{
+            viewport: Rect::new(rect.x(), rect.y(), view_size.0, view_size.1),
+            child_size: child_size,
+            hbar: if show_hbar {
+                Some(Rect::new(rect.x(), rect.y() + view_size.1 as i32, view_size.0, bar_size))
+            }else{ None },
+            vbar: if show_vbar {
+                Some(Rect::new(rect.x() + view_size.0 as i32, rect.y(), bar_size, view_size.1))
+            }else{ None },
+        }
+    }
+
+    /// Allocate the child its full size shifted by the scroll offset
+    fn alloc_child(&self) {
+        if let Some(g) = self.geometry.get() {
+            let max_x = g.child_size.0.saturating_sub(g.viewport.width());
+            let max_y = g.child_size.1.saturating_sub(g.viewport.height());
+            let (x, y) = self.offset.get();
+            let (x, y) = (min(x, max_x), min(y, max_y));
+            self.offset.set((x, y));
+
+            if let Some(ref c) = *self.child.borrow() {
+                c.alloc_rect(Rect::new(
+                    g.viewport.x() - x as i32, g.viewport.y() - y as i32, g.child_size.0, g.child_size.1));
+            }
+            self.update_bars(g);
+        }
+    }
+
+    /// Set ranges and values of the scrollbars from the geometry and the offset
+    fn update_bars(&self, g: Geometry) {
+        let (x, y) = self.offset.get();
+        if let Some(bar) = g.hbar {
+            self.hbar.alloc_rect(bar);
+        }
+        if let Some(bar) = g.vbar {
+            self.vbar.alloc_rect(bar);
+        }
+        self.hbar.configure(0.0, g.child_size.0 as f64, g.viewport.width() as f64, x as f64);
+        self.vbar.configure(0.0, g.child_size.1 as f64, g.viewport.height() as f64, y as f64);
+    }
+
+    fn update_geometry(&self) -> Option<Geometry> {
+        if self.geometry.get().is_none() {
+            if let Some(rect) = self.rect.get() {
+                self.geometry.set(Some(self.calc_geometry(rect)));
+                self.alloc_child();
+            }
+        }
+        self.geometry.get()
+    }
+}
+
+impl WidgetTrait for ScrolledWindow {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
+    fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
+        let g = self.update_geometry().expect("No rectangle allocation at rendering");
+
+        if let Some(ref c) = *self.child.borrow() {
+            let before_clip = renderer.clip_rect();
+            renderer.set_clip_rect(Some(g.viewport));
+            let result = c.render(renderer);
+            renderer.set_clip_rect(before_clip);
+            try!(result);
+        }
+
+        if g.hbar.is_some() {
+            try!(self.hbar.render(renderer));
+        }
+        if g.vbar.is_some() {
+            try!(self.vbar.render(renderer));
+        }
+        if let (Some(hbar), Some(vbar)) = (g.hbar, g.vbar) {
+            // Corner between scrollbars
+            renderer.set_draw_color(theme::get_background_color());
+            try!(renderer.fill_rect(Rect::new(vbar.x(), hbar.y(), vbar.width(), hbar.height())));
+        }
+        
+        Ok(())
+    }
+
+    fn alloc_rect(&self, rect: Rect) {
+        self.rect.set(Some(rect));
+        self.geometry.set(None);
+        self.update_geometry();
+    }
+
+    fn event_handler(&self, event: &Event) {
+        let g = match self.update_geometry() {
+            Some(g) => g,
+            None => { return; },
+        };
+        let child = self.child.borrow().clone();
+
+        if g.hbar.is_some() {
+            self.hbar.event_handler(event);
+        }
+        if g.vbar.is_some() {
+            self.vbar.event_handler(event);
+        }
+
+        match *event {
+            Event::ButtonDown((x, y), _) => {
+                if self.rect.get().map_or(false, |r| r.contains((x, y))) {
+                    // A focusable child takes the focus from the scrolled window
+                    self.grab_focus();
+                }
+
+                if g.viewport.contains((x, y)) {
+                    if let Some(c) = child {
+                        c.event_handler(event);
+                    }
+                }
+            },
+            Event::MouseWheel(pointer, (dx, dy)) => {
+                if g.viewport.contains(pointer) {
+                    // A scrollable widget inside takes the wheel first
+                    if let Some(c) = child {
+                        c.event_handler(event);
+                    }
+                    if claim_wheel() {
+                        self.scroll_by(dx * SCROLL_STEP, -dy * SCROLL_STEP);
+                    }
+                }
+            },
+            Event::KeyDown(keycode, _) => {
+                if self.has_focus() {
+                    let page = g.viewport.height() as i32;
+                    match keycode {
+                        Keycode::Up => self.scroll_by(0, -SCROLL_STEP),
+                        Keycode::Down => self.scroll_by(0, SCROLL_STEP),
+                        Keycode::Left => self.scroll_by(-SCROLL_STEP, 0),
+                        Keycode::Right => self.scroll_by(SCROLL_STEP, 0),
+                        Keycode::PageUp => self.scroll_by(0, -page),
+                        Keycode::PageDown => self.scroll_by(0, page),
+                        Keycode::Home => self.scroll_to(0, 0),
+                        Keycode::End => self.scroll_to(self.offset.get().0, ::std::u32::MAX),
+                        _ => (),
+                    }
+                }
+                if let Some(c) = child {
+                    c.event_handler(event);
+                }
+            },
+            _ => {
+                if let Some(c) = child {
+                    c.event_handler(event);
+                }
+            },
+        }
+    }
+
+    fn cancel_input(&self) {
+        self.hbar.cancel_input();
+        self.vbar.cancel_input();
+        for c in self.children() {
+            c.cancel_input();
+        }
+    }
+
+    fn preferred_width(&self) -> SizeRange {
+        let size = match *self.child.borrow() {
+            Some(ref c) => c.preferred_width(),
+            None => SizeRange::default(),
+        };
+        let bar_size = if self.vpolicy.get() == ScrollPolicy::Always { get_bar_size() }else{ 0 };
+        
+        if self.hpolicy.get() == ScrollPolicy::Never {
+            size.add(bar_size)
+        }else{
+            SizeRange::new(get_bar_size(), size.natural).add(bar_size)
+        }
+    }
+
+    fn preferred_height_for_width(&self, width: u32) -> SizeRange {
+        let size = match *self.child.borrow() {
+            Some(ref c) => c.preferred_height_for_width(width),
+            None => SizeRange::default(),
+        };
+        let bar_size = if self.hpolicy.get() == ScrollPolicy::Always { get_bar_size() }else{ 0 };
+        
+        if self.vpolicy.get() == ScrollPolicy::Never {
+            size.add(bar_size)
+        }else{
+            SizeRange::new(get_bar_size(), size.natural).add(bar_size)
+        }
+    }
+}
+
+impl ContainerTrait for ScrolledWindow {
+    fn with_children<F: FnMut(&AnyWidget)>(&self, f: F) {
+        let mut f = f;
+
+        if let Some(ref c) = *self.child.borrow() {
+            f(c);
+        }
+    }
+}
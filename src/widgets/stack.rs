@@ -0,0 +1,309 @@
+
+use std::rc::Rc;
+use std::cell::{RefCell, Cell};
+use std::time::{Duration, Instant};
+use ::sdl2::render::{BlendMode, Texture};
+use ::sdl2::pixels::PixelFormatEnum;
+
+use prelude::*;
+use theme;
+use enums::StackTransition;
+use widgets::aligned_rect;
+use uicontext::{request_redraw, request_relayout};
+
+/// Container which shows one of its named children at a time
+pub struct Stack {
+    common: WidgetCommon,
+    rect: Cell<Option<Rect>>,
+    children: RefCell<Vec<(String, AnyWidget)>>,
+    visible: RefCell<Option<String>>,
+    transition_type: Cell<StackTransition>,
+    transition_duration: Cell<Duration>,
+    // The previously visible child and the time the transition started
+    transition: RefCell<Option<(String, Instant)>>,
+}
+
+impl Stack {
+    pub fn new() -> Rc<Stack> {
+        Rc::new(Stack{
+            common: WidgetCommon::new(),
+            rect: Cell::new(None),
+            children: RefCell::new(Vec::new()),
+            visible: RefCell::new(None),
+            transition_type: Cell::new(StackTransition::None),
+            transition_duration: Cell::new(Duration::from_millis(200)),
+            transition: RefCell::new(None),
+        })
+    }
+
+    /// Add a child with a name. The first child becomes visible.
+    /// If a child with the same name exists, it is replaced.
+    pub fn add_named<W: Into<AnyWidget>>(&self, w: W, name: &str) {
+        let w = w.into();
+        {
+            let mut children = self.children.borrow_mut();
+            if let Some(c) = children.iter_mut().find(|c| c.0 == name) {
+                c.1 = w;
+            }else{
+                children.push((name.to_owned(), w));
+            }
+        }
+        if self.visible.borrow().is_none() {
+            *self.visible.borrow_mut() = Some(name.to_owned());
+        }
+        request_relayout();
+    }
+
+    /// Remove the child with the name. Returns false if it does not exist.
+    pub fn remove_named(&self, name: &str) -> bool {
+        let removed = {
+            let mut children = self.children.borrow_mut();
+            match children.iter().position(|c| c.0 == name) {
+                Some(i) => { children.remove(i); true },
+                None => false,
+            }
+        };
+        if !removed {
+            return false;
+        }
+
+        *self.transition.borrow_mut() = None;
+        let is_visible = self.visible.borrow().as_ref().map_or(false, |v| v == name);
+        if is_visible {
+            *self.visible.borrow_mut() = self.children.borrow().first().map(|c| c.0.clone());
+        }
+        request_relayout();
+        true
+    }
+
+    /// Show the child with the name using the current transition.
+    /// Returns false if it does not exist.
+    pub fn set_visible_child(&self, name: &str) -> bool {
+        if self.get_child_by_name(name).is_none() {
+            return false;
+        }
+        let prev = self.visible.borrow().clone();
+        if prev.as_ref().map_or(false, |v| v == name) {
+            return true;
+        }
+
+        *self.visible.borrow_mut() = Some(name.to_owned());
+        *self.transition.borrow_mut() = match prev {
+            Some(prev) if self.transition_type.get() != StackTransition::None => {
+                Some((prev, Instant::now()))
+            },
+            _ => None,
+        };
+        self.alloc_children();
+        request_redraw();
+        true
+    }
+
+    pub fn get_visible_child_name(&self) -> Option<String> {
+        self.visible.borrow().clone()
+    }
+
+    pub fn get_visible_child(&self) -> Option<AnyWidget> {
+        let visible = self.visible.borrow();
+        visible.as_ref().and_then(|name| self.get_child_by_name(name))
+    }
+
+    pub fn get_child_by_name(&self, name: &str) -> Option<AnyWidget> {
+        self.children.borrow().iter().find(|c| c.0 == name).map(|c| c.1.clone())
+    }
+
+    /// Set the animation used when the visible child is changed
+    pub fn set_transition_type(&self, transition_type: StackTransition) {
+        self.transition_type.set(transition_type);
+    }
+
+    pub fn get_transition_type(&self) -> StackTransition {
+        self.transition_type.get()
+    }
+
+    pub fn set_transition_duration(&self, duration: Duration) {
+        self.transition_duration.set(duration);
+    }
+
+    pub fn get_transition_duration(&self) -> Duration {
+        self.transition_duration.get()
+    }
+
+    /// Returns the previous child and the progress of the transition from 0.0 to 1.0
+    fn transition_progress(&self) -> Option<(AnyWidget, f64)> {
+        let finished = match *self.transition.borrow() {
+            Some((ref prev, start)) => {
+                let duration = self.transition_duration.get();
+                let elapsed = start.elapsed();
+                if elapsed < duration {
+                    let progress = duration_to_secs(elapsed) / duration_to_secs(duration);
+                    return self.get_child_by_name(prev).map(|w| (w, progress));
+                }
+                true
+            },
+            None => false,
+        };
+
+        if finished {
+            *self.transition.borrow_mut() = None;
+            self.alloc_children();
+        }
+        None
+    }
+
+    /// Allocate the rect to children. While sliding, children are shifted.
+    fn alloc_children(&self) {
+        let rect = match self.rect.get() {
+            Some(rect) => rect,
+            None => { return; },
+        };
+
+        for &(_, ref c) in self.children.borrow().iter() {
+            c.alloc_rect(aligned_rect(c, rect));
+        }
+
+        if let Some((prev, progress)) = self.transition_progress() {
+            let (dx, dy) = match self.transition_type.get() {
+                StackTransition::SlideLeft => (rect.width() as i32, 0),
+                StackTransition::SlideRight => (-(rect.width() as i32), 0),
+                StackTransition::SlideUp => (0, rect.height() as i32),
+                StackTransition::SlideDown => (0, -(rect.height() as i32)),
+                _ => { return; },
+            };
+            // Ease out
+            let p = 1.0 - (1.0 - progress) * (1.0 - progress);
+            let shift = |r: Rect, f: f64| Rect::new(
+                r.x() + (dx as f64 * f) as i32, r.y() + (dy as f64 * f) as i32, r.width(), r.height());
+            
+            prev.alloc_rect(shift(aligned_rect(&prev, rect), -p));
+            if let Some(visible) = self.get_visible_child() {
+                visible.alloc_rect(shift(aligned_rect(&visible, rect), 1.0 - p));
+            }
+        }
+    }
+}
+
+/// Render the widget on a texture of the output size filled with the background color.
+/// The widget is drawn at the same position as on the output.
+fn render_to_texture(renderer: &mut Renderer, w: &AnyWidget, rect: Rect) -> Result<Option<Texture>, String> {
+    let (width, height) = try!(renderer.output_size());
+    let texture = try!(renderer.create_texture_target(PixelFormatEnum::ARGB8888, width, height)
+                       .map_err(|e| e.to_string()));
+    // A Stack being faded may be inside another one, so the current target is restored
+    let old_target = match renderer.render_target() {
+        Some(mut target) => try!(target.set(texture)),
+        None => { return Err("Render targets are not supported".to_owned()); },
+    };
+
+    renderer.set_draw_color(theme::get_background_color());
+    renderer.clear();
+    renderer.set_clip_rect(Some(rect));
+    let result = w.render(renderer);
+
+    let texture = {
+        let mut target = renderer.render_target().unwrap();
+        match old_target {
+            Some(old_target) => try!(target.set(old_target)),
+            None => try!(target.reset()),
+        }
+    };
+    renderer.set_clip_rect(Some(rect));
+    try!(result);
+    Ok(texture)
+}
+
+fn duration_to_secs(d: Duration) -> f64 {
+    d.as_secs() as f64 + d.subsec_nanos() as f64 * 1e-9
+}
+
+impl WidgetTrait for Stack {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
+    fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
+        let rect = self.rect.get().expect("No rectangle allocation at rendering");
+        let visible = self.get_visible_child();
+
+        let transition = self.transition_progress();
+        if transition.is_none() {
+            if let Some(visible) = visible {
+                try!(visible.render(renderer));
+            }
+            return Ok(());
+        }
+
+        let (prev, progress) = transition.unwrap();
+        self.alloc_children();
+        let before_clip = renderer.clip_rect();
+        renderer.set_clip_rect(Some(rect));
+
+        let result = if self.transition_type.get() == StackTransition::Crossfade {
+            // Draw the new child over the previous child with increasing opacity
+            render_to_texture(renderer, &prev, rect).and_then(|prev_texture| {
+                let visible_texture = match visible {
+                    Some(ref w) => try!(render_to_texture(renderer, w, rect)),
+                    None => None,
+                };
+                if let Some(texture) = prev_texture {
+                    try!(renderer.copy(&texture, Some(rect), Some(rect)));
+                }
+                if let Some(mut texture) = visible_texture {
+                    texture.set_blend_mode(BlendMode::Blend);
+                    texture.set_alpha_mod((progress * 255.0) as u8);
+                    try!(renderer.copy(&texture, Some(rect), Some(rect)));
+                }
+                Ok(())
+            })
+        }else{
+            let mut result = prev.render(renderer);
+            if let Some(visible) = visible {
+                result = result.and(visible.render(renderer));
+            }
+            result
+        };
+
+        renderer.set_clip_rect(before_clip);
+        request_redraw();
+        result
+    }
+
+    fn alloc_rect(&self, rect: Rect) {
+        self.rect.set(Some(rect));
+        self.alloc_children();
+    }
+
+    fn event_handler(&self, event: &Event) {
+        if let Some(visible) = self.get_visible_child() {
+            visible.event_handler(event);
+        }
+    }
+
+    fn cancel_input(&self) {
+        for c in self.children() {
+            c.cancel_input();
+        }
+    }
+
+    fn preferred_width(&self) -> SizeRange {
+        let mut size = SizeRange::default();
+        self.with_children(|c| size = size.max(c.preferred_width()));
+        size
+    }
+
+    fn preferred_height_for_width(&self, width: u32) -> SizeRange {
+        let mut size = SizeRange::default();
+        self.with_children(|c| size = size.max(c.preferred_height_for_width(width)));
+        size
+    }
+}
+
+impl ContainerTrait for Stack {
+    fn with_children<F: FnMut(&AnyWidget)>(&self, f: F) {
+        let mut f = f;
+
+        for &(_, ref c) in self.children.borrow().iter() {
+            f(c);
+        }
+    }
+}
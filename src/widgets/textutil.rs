@@ -0,0 +1,124 @@
+//! Caret movement in text edited by Entry and TextView.
+//! Positions are byte indices into the string.
+
+use unicode_segmentation::{GraphemeCursor, UnicodeSegmentation};
+
+/// The grapheme boundary after pos
+pub fn next_boundary(s: &str, pos: usize) -> usize {
+    let mut cursor = GraphemeCursor::new(pos, s.len(), true);
+    match cursor.next_boundary(s, 0) {
+        Ok(Some(next)) => next,
+        _ => s.len(),
+    }
+}
+
+/// The grapheme boundary before pos
+pub fn prev_boundary(s: &str, pos: usize) -> usize {
+    let mut cursor = GraphemeCursor::new(pos, s.len(), true);
+    match cursor.prev_boundary(s, 0) {
+        Ok(Some(prev)) => prev,
+        _ => 0,
+    }
+}
+
+/// All grapheme boundaries including both ends
+pub fn boundaries(s: &str) -> Vec<usize> {
+    let mut result: Vec<usize> = s.grapheme_indices(true).map(|(i, _)| i).collect();
+    result.push(s.len());
+    result
+}
+
+fn is_word(segment: &str) -> bool {
+    segment.chars().any(|c| c.is_alphanumeric())
+}
+
+/// The end of the word at or after pos
+pub fn next_word_end(s: &str, pos: usize) -> usize {
+    s.split_word_bound_indices()
+        .map(|(i, w)| (i + w.len(), w))
+        .find(|&(end, w)| end > pos && is_word(w))
+        .map_or(s.len(), |(end, _)| end)
+}
+
+/// The start of the word at or before pos
+pub fn prev_word_start(s: &str, pos: usize) -> usize {
+    s.split_word_bound_indices()
+        .take_while(|&(i, _)| i < pos)
+        .filter(|&(_, w)| is_word(w))
+        .last()
+        .map_or(0, |(i, _)| i)
+}
+
+/// Byte index after the first n characters
+pub fn char_index(s: &str, n: usize) -> usize {
+    s.char_indices().nth(n).map_or(s.len(), |(i, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combining_marks() {
+        // "e" with a combining acute accent, then "x"
+        let s = "e\u{301}x";
+        assert_eq!(next_boundary(s, 0), 3);
+        assert_eq!(prev_boundary(s, 3), 0);
+        assert_eq!(boundaries(s), vec![0, 3, 4]);
+    }
+
+    #[test]
+    fn zwj_emoji() {
+        // Family of a man, a woman and a girl joined by zero width joiners
+        let s = "a\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}b";
+        assert_eq!(next_boundary(s, 1), s.len() - 1);
+        assert_eq!(prev_boundary(s, s.len() - 1), 1);
+        assert_eq!(boundaries(s), vec![0, 1, s.len() - 1, s.len()]);
+    }
+
+    #[test]
+    fn flags() {
+        // Two flags made of regional indicator pairs
+        let s = "\u{1F1EF}\u{1F1F5}\u{1F1FA}\u{1F1F8}";
+        assert_eq!(next_boundary(s, 0), 8);
+        assert_eq!(next_boundary(s, 8), 16);
+        assert_eq!(prev_boundary(s, 16), 8);
+        assert_eq!(boundaries(s), vec![0, 8, 16]);
+    }
+
+    #[test]
+    fn ends() {
+        assert_eq!(next_boundary("ab", 2), 2);
+        assert_eq!(prev_boundary("ab", 0), 0);
+        assert_eq!(boundaries(""), vec![0]);
+        assert_eq!(next_boundary("\r\nx", 0), 2);
+    }
+
+    #[test]
+    fn word_movement() {
+        let s = "hello, world  can't";
+        assert_eq!(next_word_end(s, 0), 5);
+        assert_eq!(next_word_end(s, 5), 12);
+        assert_eq!(next_word_end(s, 12), s.len());
+        assert_eq!(next_word_end(s, s.len()), s.len());
+        assert_eq!(prev_word_start(s, s.len()), 14);
+        assert_eq!(prev_word_start(s, 14), 7);
+        assert_eq!(prev_word_start(s, 9), 7);
+        assert_eq!(prev_word_start(s, 7), 0);
+        assert_eq!(prev_word_start(s, 0), 0);
+    }
+
+    #[test]
+    fn word_with_marks() {
+        // The accent stays in the word
+        let s = "cafe\u{301} au";
+        assert_eq!(next_word_end(s, 0), 6);
+        assert_eq!(prev_word_start(s, 6), 0);
+    }
+
+    #[test]
+    fn char_indices() {
+        assert_eq!(char_index("a\u{e9}b", 2), 3);
+        assert_eq!(char_index("ab", 5), 2);
+    }
+}
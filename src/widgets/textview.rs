@@ -0,0 +1,967 @@
+use std::rc::{Rc, Weak};
+use std::cell::{RefCell, Cell};
+use std::cmp::{min, max};
+use ::sdl2::mouse::Mouse;
+use ::sdl2::keyboard::{Keycode, Mod};
+
+use prelude::*;
+use theme;
+use event::{ctrl_pressed, shift_pressed};
+use uicontext::{request_redraw, claim_wheel};
+use widgets::{rendering_insensitive, VScrollBar};
+use super::textutil;
+
+const PADDING: u32 = 3;
+const NATURAL_WIDTH: u32 = 300;
+const NATURAL_ROWS: u32 = 8;
+const WHEEL_ROWS: i32 = 3;
+// Width of the selection drawn for a selected line break
+const NEWLINE_WIDTH: u32 = 4;
+// Number of changes a buffer remembers for updating the layouts of its views
+const MAX_EDITS: usize = 64;
+
+fn text_width(s: &str) -> u32 {
+    if s.is_empty() {
+        return 0;
+    }
+    theme::get_default_font().size_of(s).map(|size| size.0).unwrap_or(0)
+}
+
+fn line_height() -> u32 {
+    max(theme::get_default_font().height(), 1) as u32
+}
+
+/// Position in a TextBuffer. The index is a byte index in the line.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TextPos {
+    pub line: usize,
+    pub index: usize,
+}
+
+impl TextPos {
+    pub fn new(line: usize, index: usize) -> TextPos {
+        TextPos{ line: line, index: index }
+    }
+}
+
+/// Lines [line, line + removed) were replaced by lines [line, line + inserted)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct LineEdit {
+    line: usize,
+    removed: usize,
+    inserted: usize,
+}
+
+impl LineEdit {
+    /// One edit doing this edit and then the next edit
+    fn then(self, next: LineEdit) -> LineEdit {
+        let line = min(self.line, next.line);
+        // The end of the changed lines before the next edit
+        let end = max(self.line + self.inserted, next.line + next.removed);
+        LineEdit{
+            line: line,
+            removed: end - self.inserted + self.removed - line,
+            inserted: end - next.removed + next.inserted - line,
+        }
+    }
+}
+
+/// Text stored as lines. A buffer can be shared by several TextViews.
+pub struct TextBuffer {
+    lines: RefCell<Vec<String>>,
+    version: Cell<u64>, // Increased at every change so that views can update their layouts
+    edits: RefCell<Vec<(u64, LineEdit)>>, // Recent changes and the versions after them
+    callback_changed: RefCell<Option<Box<Fn() + 'static>>>,
+}
+
+impl TextBuffer {
+    pub fn new() -> Rc<TextBuffer> {
+        Rc::new(TextBuffer{
+            lines: RefCell::new(vec![String::new()]),
+            version: Cell::new(0),
+            edits: RefCell::new(Vec::new()),
+            callback_changed: RefCell::new(None),
+        })
+    }
+
+    /// Replace the whole text
+    pub fn set_text(&self, text: &str) {
+        let removed = self.lines.borrow().len();
+        *self.lines.borrow_mut() = text.split('\n').map(|l| l.to_owned()).collect();
+        let inserted = self.lines.borrow().len();
+        self.changed(LineEdit{ line: 0, removed: removed, inserted: inserted });
+    }
+
+    /// The whole text joined with "\n"
+    pub fn get_text(&self) -> String {
+        self.lines.borrow().join("\n")
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.lines.borrow().len()
+    }
+
+    pub fn get_line(&self, line: usize) -> Option<String> {
+        self.lines.borrow().get(line).cloned()
+    }
+
+    /// The position after the last character
+    pub fn end_pos(&self) -> TextPos {
+        let lines = self.lines.borrow();
+        TextPos::new(lines.len() - 1, lines[lines.len() - 1].len())
+    }
+
+    /// Move the position into the text and onto a character boundary
+    pub fn clamp_pos(&self, pos: TextPos) -> TextPos {
+        let lines = self.lines.borrow();
+        let line = min(pos.line, lines.len() - 1);
+        let s = &lines[line];
+        let mut index = min(pos.index, s.len());
+        while !s.is_char_boundary(index) {
+            index -= 1;
+        }
+        TextPos::new(line, index)
+    }
+
+    /// Text between two positions. Lines are joined with "\n".
+    pub fn get_range(&self, start: TextPos, end: TextPos) -> String {
+        let (start, end) = (self.clamp_pos(min(start, end)), self.clamp_pos(max(start, end)));
+        let lines = self.lines.borrow();
+        if start.line == end.line {
+            return lines[start.line][start.index..end.index].to_owned();
+        }
+        let mut result = lines[start.line][start.index..].to_owned();
+        for line in &lines[(start.line + 1)..end.line] {
+            result.push('\n');
+            result.push_str(line);
+        }
+        result.push('\n');
+        result.push_str(&lines[end.line][..end.index]);
+        result
+    }
+
+    /// Insert text and return the position after it
+    pub fn insert(&self, pos: TextPos, text: &str) -> TextPos {
+        let pos = self.clamp_pos(pos);
+        let end = {
+            let mut lines = self.lines.borrow_mut();
+            let tail = lines[pos.line].split_off(pos.index);
+            let mut parts = text.split('\n');
+            lines[pos.line].push_str(parts.next().unwrap());
+
+            let mut line = pos.line;
+            for part in parts {
+                line += 1;
+                lines.insert(line, part.to_owned());
+            }
+            let index = lines[line].len();
+            lines[line].push_str(&tail);
+            TextPos::new(line, index)
+        };
+        self.changed(LineEdit{ line: pos.line, removed: 1, inserted: end.line - pos.line + 1 });
+        end
+    }
+
+    /// Delete the text between two positions
+    pub fn delete(&self, start: TextPos, end: TextPos) {
+        let (start, end) = (self.clamp_pos(min(start, end)), self.clamp_pos(max(start, end)));
+        if start == end {
+            return;
+        }
+        {
+            let mut lines = self.lines.borrow_mut();
+            let tail = lines[end.line][end.index..].to_owned();
+            lines[start.line].truncate(start.index);
+            lines[start.line].push_str(&tail);
+            lines.drain((start.line + 1)..(end.line + 1));
+        }
+        self.changed(LineEdit{ line: start.line, removed: end.line - start.line + 1, inserted: 1 });
+    }
+
+    /// Add text at the end
+    pub fn append(&self, text: &str) {
+        let end = self.end_pos();
+        self.insert(end, text);
+    }
+
+    /// Set a callback called when the text is changed
+    pub fn on_changed<F>(&self, f: F) where F: Fn() + 'static {
+        *self.callback_changed.borrow_mut() = Some(Box::new(f));
+    }
+
+    fn with_line<R, F: FnOnce(&str) -> R>(&self, line: usize, f: F) -> R {
+        f(&self.lines.borrow()[line])
+    }
+
+    /// The lines changed after the version, or None if the buffer does not remember it
+    fn edit_since(&self, version: u64) -> Option<LineEdit> {
+        let edits = self.edits.borrow();
+        let first = match edits.iter().position(|e| e.0 == version.wrapping_add(1)) {
+            Some(i) => i,
+            None => { return None; },
+        };
+        let mut result = edits[first].1;
+        for e in &edits[(first + 1)..] {
+            result = result.then(e.1);
+        }
+        Some(result)
+    }
+
+    fn changed(&self, edit: LineEdit) {
+        self.version.set(self.version.get().wrapping_add(1));
+        {
+            let mut edits = self.edits.borrow_mut();
+            if edits.len() >= MAX_EDITS {
+                edits.remove(0);
+            }
+            edits.push((self.version.get(), edit));
+        }
+        request_redraw();
+        if let Some(ref f) = *self.callback_changed.borrow() {
+            f();
+        }
+    }
+}
+
+/// A part of a line shown in one row
+#[derive(Clone, Copy, Debug)]
+struct Row {
+    line: usize,
+    start: usize,
+    end: usize,
+}
+
+/// Rects and sizes decided by the allocated rect and the text
+struct Geometry {
+    text: Rect,
+    bar: Option<Rect>,
+    visible_rows: usize,
+}
+
+/// Split a line into rows narrower than width. Rows are broken after spaces if possible.
+fn wrap_line(s: &str, width: u32) -> Vec<(usize, usize)> {
+    let bounds = textutil::boundaries(s);
+    let last = bounds.len() - 1;
+    let fits = |a: usize, b: usize| text_width(&s[bounds[a]..bounds[b]]) <= width;
+
+    let mut rows = Vec::new();
+    let mut start = 0;
+    while start < last {
+        // Find the most graphemes which fit by doubling the count and then bisecting,
+        // so that the measured text stays around the length of a row.
+        // A row has at least one grapheme even if it is too wide.
+        let mut fit = start + 1;
+        let mut step = 1;
+        while fit + step <= last && fits(start, fit + step) {
+            fit += step;
+            step *= 2;
+        }
+        let mut too_wide = min(fit + step, last + 1);
+        while too_wide - fit > 1 {
+            let mid = (fit + too_wide) / 2;
+            if fits(start, mid) {
+                fit = mid;
+            }else{
+                too_wide = mid;
+            }
+        }
+
+        let mut end = fit;
+        if fit < last {
+            if let Some(space) = (start + 1..fit + 1).rev().find(|i| s[..bounds[*i]].ends_with(' ')) {
+                end = space;
+            }
+        }
+        rows.push((bounds[start], bounds[end]));
+        start = end;
+    }
+    if rows.is_empty() {
+        rows.push((0, 0));
+    }
+    rows
+}
+
+/// Multi-line text viewer and editor
+pub struct TextView {
+    common: WidgetCommon,
+    rect: Cell<Option<Rect>>,
+    buffer: Rc<TextBuffer>,
+    vbar: Rc<VScrollBar>,
+    rows: RefCell<Vec<Row>>,
+    layout_key: Cell<Option<(u64, u32, bool)>>, // Buffer version, width and wrap of the rows
+    has_bar: Cell<bool>, // The rows overflow and the scrollbar is shown
+    bar_state: Cell<(usize, usize, usize)>, // Rows, visible rows and the scroll row given to the bar
+    caret: Cell<TextPos>,
+    anchor: Cell<Option<TextPos>>, // The other end of the selection
+    caret_x: Cell<Option<u32>>, // Column kept while moving up and down
+    scroll_row: Cell<usize>, // The first visible row
+    scroll_x: Cell<u32>, // Width hidden at the left when not wrapping
+    follow_caret: Cell<bool>, // Scroll to the caret at next rendering
+    wrap: Cell<bool>,
+    editable: Cell<bool>,
+    dragging: Cell<bool>,
+}
+
+impl TextView {
+    pub fn new() -> Rc<TextView> {
+        TextView::new_with_buffer(TextBuffer::new())
+    }
+
+    /// Create a view showing the buffer
+    pub fn new_with_buffer(buffer: Rc<TextBuffer>) -> Rc<TextView> {
+        let view = Rc::new(TextView{
+            common: WidgetCommon::new(),
+            rect: Cell::new(None),
+            buffer: buffer,
+            vbar: VScrollBar::new(),
+            rows: RefCell::new(Vec::new()),
+            layout_key: Cell::new(None),
+            has_bar: Cell::new(false),
+            bar_state: Cell::new((0, 0, 0)),
+            caret: Cell::new(TextPos::default()),
+            anchor: Cell::new(None),
+            caret_x: Cell::new(None),
+            scroll_row: Cell::new(0),
+            scroll_x: Cell::new(0),
+            follow_caret: Cell::new(false),
+            wrap: Cell::new(false),
+            editable: Cell::new(true),
+            dragging: Cell::new(false),
+        });
+        view.vbar.set_step(1.0);
+
+        let weak: Weak<TextView> = Rc::downgrade(&view);
+        view.vbar.on_value_changed(move |v| {
+            if let Some(view) = weak.upgrade() {
+                view.scroll_row.set(v.round() as usize);
+                request_redraw();
+            }
+        });
+        view
+    }
+
+    pub fn get_buffer(&self) -> Rc<TextBuffer> {
+        self.buffer.clone()
+    }
+
+    /// If wrap is true, long lines are broken to fit the width.
+    /// Otherwise the text scrolls horizontally with the caret.
+    pub fn set_wrap(&self, wrap: bool) {
+        self.wrap.set(wrap);
+        self.scroll_x.set(0);
+        request_redraw();
+    }
+
+    pub fn get_wrap(&self) -> bool {
+        self.wrap.get()
+    }
+
+    /// If editable is false, the text can be selected but not changed
+    pub fn set_editable(&self, editable: bool) {
+        self.editable.set(editable);
+        request_redraw();
+    }
+
+    pub fn get_editable(&self) -> bool {
+        self.editable.get()
+    }
+
+    pub fn get_caret(&self) -> TextPos {
+        self.caret.get()
+    }
+
+    /// Move the caret, clear the selection and scroll to the caret
+    pub fn set_caret(&self, pos: TextPos) {
+        let pos = self.buffer.clamp_pos(pos);
+        self.move_caret(pos, false);
+    }
+
+    /// Ordered positions of the selected text
+    pub fn get_selection(&self) -> Option<(TextPos, TextPos)> {
+        let caret = self.caret.get();
+        match self.anchor.get() {
+            Some(a) if a != caret => Some((min(a, caret), max(a, caret))),
+            _ => None,
+        }
+    }
+
+    pub fn select_all(&self) {
+        self.anchor.set(Some(TextPos::default()));
+        self.caret.set(self.buffer.end_pos());
+        self.follow_caret.set(true);
+        request_redraw();
+    }
+
+    /// Show the last line. Useful for logs.
+    pub fn scroll_to_end(&self) {
+        self.scroll_row.set(::std::usize::MAX);
+        request_redraw();
+    }
+
+    fn line_rows(&self, line: usize, s: &str, width: u32) -> Vec<Row> {
+        if self.wrap.get() {
+            wrap_line(s, width).into_iter().map(|(start, end)| Row{ line: line, start: start, end: end }).collect()
+        }else{
+            vec![Row{ line: line, start: 0, end: s.len() }]
+        }
+    }
+
+    /// Update the rows if the buffer or the width is changed.
+    /// Only the changed lines are wrapped again.
+    fn layout(&self, width: u32) {
+        // Rows without wrapping do not depend on the width
+        let width = if self.wrap.get() { width }else{ 0 };
+        let version = self.buffer.version.get();
+        let key = Some((version, width, self.wrap.get()));
+        let old_key = self.layout_key.get();
+        if old_key == key {
+            return;
+        }
+        self.layout_key.set(key);
+
+        let lines = self.buffer.lines.borrow();
+        let mut rows = self.rows.borrow_mut();
+        let edit = match old_key {
+            Some((old_version, old_width, old_wrap)) if old_width == width && old_wrap == self.wrap.get() => {
+                self.buffer.edit_since(old_version)
+            },
+            _ => None,
+        };
+        let edit = match edit {
+            Some(e) => e,
+            None => {
+                rows.clear();
+                for (i, s) in lines.iter().enumerate() {
+                    rows.extend(self.line_rows(i, s, width));
+                }
+                return;
+            },
+        };
+
+        let row_index = |rows: &[Row], line: usize| {
+            match rows.binary_search_by(|r| (r.line, r.start).cmp(&(line, 0))) {
+                Ok(i) | Err(i) => i,
+            }
+        };
+        let first = row_index(&rows, edit.line);
+        let last = row_index(&rows, edit.line + edit.removed);
+        let mut new_rows = Vec::new();
+        for i in edit.line..(edit.line + edit.inserted) {
+            new_rows.extend(self.line_rows(i, &lines[i], width));
+        }
+        let n_new = new_rows.len();
+        rows.splice(first..last, new_rows);
+        if edit.inserted != edit.removed {
+            for r in rows[(first + n_new)..].iter_mut() {
+                r.line += edit.inserted;
+                r.line -= edit.removed;
+            }
+        }
+    }
+
+    /// Update the rows and decide where the text and the scrollbar are
+    fn geometry(&self) -> Option<Geometry> {
+        let rect = match self.rect.get() {
+            Some(r) => r,
+            None => { return None; },
+        };
+        // The buffer may be changed by others
+        self.caret.set(self.buffer.clamp_pos(self.caret.get()));
+        self.anchor.set(self.anchor.get().map(|a| self.buffer.clamp_pos(a)));
+
+        let mut text = Rect::new(rect.x() + PADDING as i32, rect.y() + PADDING as i32,
+                                 rect.width().saturating_sub(PADDING * 2),
+                                 rect.height().saturating_sub(PADDING * 2));
+        let visible_rows = max(text.height() / line_height(), 1) as usize;
+        let bar_size = min(theme::get_theme_layout().size_scrollbar, rect.width().saturating_sub(2));
+        let narrow_width = text.width().saturating_sub(bar_size);
+
+        // Show the scrollbar only when the rows overflow.
+        // The shown bar is kept while the rows overflow the narrowed text, so that
+        // the rows are not wrapped for both widths every time.
+        if self.has_bar.get() {
+            self.layout(narrow_width);
+            if self.rows.borrow().len() <= visible_rows {
+                self.has_bar.set(false);
+            }
+        }
+        if !self.has_bar.get() {
+            self.layout(text.width());
+            if self.rows.borrow().len() > visible_rows {
+                self.has_bar.set(true);
+                self.layout(narrow_width);
+            }
+        }
+        let mut bar = None;
+        if self.has_bar.get() {
+            text.set_width(narrow_width);
+            bar = Some(Rect::new(rect.right() - 1 - bar_size as i32, rect.y() + 1,
+                                 bar_size, rect.height().saturating_sub(2)));
+        }
+
+        let n_rows = self.rows.borrow().len();
+        self.scroll_row.set(min(self.scroll_row.get(), n_rows.saturating_sub(visible_rows)));
+        Some(Geometry{ text: text, bar: bar, visible_rows: visible_rows })
+    }
+
+    /// Give the scroll state to the scrollbar if it is changed
+    fn sync_bar(&self, g: &Geometry) {
+        if let Some(bar) = g.bar {
+            self.vbar.alloc_rect(bar);
+            let state = (self.rows.borrow().len(), g.visible_rows, self.scroll_row.get());
+            if self.bar_state.get() != state {
+                self.bar_state.set(state);
+                self.vbar.configure(0.0, state.0 as f64, state.1 as f64, state.2 as f64);
+            }
+        }
+    }
+
+    /// Index of the row which shows the position
+    fn row_of(&self, pos: TextPos) -> usize {
+        let rows = self.rows.borrow();
+        match rows.binary_search_by(|r| (r.line, r.start).cmp(&(pos.line, pos.index))) {
+            Ok(i) => i,
+            Err(i) => i.saturating_sub(1),
+        }
+    }
+
+    fn is_last_row_of_line(&self, row: usize) -> bool {
+        let rows = self.rows.borrow();
+        row + 1 >= rows.len() || rows[row + 1].line != rows[row].line
+    }
+
+    /// The last position where the caret can be put in the row
+    fn row_end(&self, row: usize) -> TextPos {
+        let r = self.rows.borrow()[row];
+        if self.is_last_row_of_line(row) || r.end == r.start {
+            TextPos::new(r.line, r.end)
+        }else{
+            // The end of a wrapped row is the start of the next row
+            let index = self.buffer.with_line(r.line, |s| textutil::prev_boundary(s, r.end));
+            TextPos::new(r.line, max(index, r.start))
+        }
+    }
+
+    /// Distance from the row start to the position
+    fn x_in_row(&self, pos: TextPos) -> u32 {
+        let r = self.rows.borrow()[self.row_of(pos)];
+        self.buffer.with_line(r.line, |s| text_width(&s[r.start..max(pos.index, r.start)]))
+    }
+
+    /// The grapheme boundary in the row nearest to x
+    fn pos_in_row(&self, row: usize, x: i32) -> TextPos {
+        let r = self.rows.borrow()[row];
+        let end = self.row_end(row).index;
+        let index = self.buffer.with_line(r.line, |s| {
+            let mut result = r.start;
+            let mut prev_x = 0;
+            for b in textutil::boundaries(&s[r.start..end]) {
+                let b_x = text_width(&s[r.start..(r.start + b)]) as i32;
+                if b_x > x {
+                    // Pick the closer of this and the previous boundary
+                    if b_x - x < x - prev_x {
+                        result = r.start + b;
+                    }
+                    break;
+                }
+                result = r.start + b;
+                prev_x = b_x;
+            }
+            result
+        });
+        TextPos::new(r.line, index)
+    }
+
+    /// The position under the point. Points above or below the text give the nearest row.
+    fn pos_at(&self, g: &Geometry, p: (i32, i32)) -> TextPos {
+        let line_height = line_height() as i32;
+        let rel = p.1 - g.text.y();
+        let row = if rel < 0 {
+            self.scroll_row.get().saturating_sub(1)
+        }else{
+            self.scroll_row.get() + (rel / line_height) as usize
+        };
+        let row = min(row, self.rows.borrow().len() - 1);
+        let scroll_x = if self.wrap.get() { 0 }else{ self.scroll_x.get() as i32 };
+        self.pos_in_row(row, p.0 - g.text.x() + scroll_x)
+    }
+
+    /// Move the caret. With extend, the selection is extended to the new position.
+    fn move_caret(&self, pos: TextPos, extend: bool) {
+        if extend {
+            if self.anchor.get().is_none() {
+                self.anchor.set(Some(self.caret.get()));
+            }
+        }else{
+            self.anchor.set(None);
+        }
+        self.caret.set(pos);
+        self.caret_x.set(None);
+        self.follow_caret.set(true);
+        request_redraw();
+    }
+
+    /// Move the caret by rows keeping its column
+    fn move_rows(&self, n: isize, extend: bool) {
+        let row = self.row_of(self.caret.get()) as isize + n;
+        let n_rows = self.rows.borrow().len() as isize;
+        let x = self.caret_x.get().unwrap_or_else(|| self.x_in_row(self.caret.get()));
+        let pos = if row < 0 {
+            TextPos::default()
+        }else if row >= n_rows {
+            self.buffer.end_pos()
+        }else{
+            self.pos_in_row(row as usize, x as i32)
+        };
+        self.move_caret(pos, extend);
+        self.caret_x.set(Some(x));
+    }
+
+    /// Position the caret moves to by an arrow key. It goes across line breaks.
+    fn arrow_target(&self, forward: bool, word: bool) -> TextPos {
+        let caret = self.caret.get();
+        let n_lines = self.buffer.line_count();
+        let len = self.buffer.with_line(caret.line, |s| s.len());
+        if !forward && caret.index == 0 {
+            if caret.line == 0 {
+                return caret;
+            }
+            let line = caret.line - 1;
+            return TextPos::new(line, self.buffer.with_line(line, |s| s.len()));
+        }
+        if forward && caret.index == len {
+            return if caret.line + 1 < n_lines { TextPos::new(caret.line + 1, 0) }else{ caret };
+        }
+        let index = self.buffer.with_line(caret.line, |s| match (forward, word) {
+            (false, false) => textutil::prev_boundary(s, caret.index),
+            (true, false) => textutil::next_boundary(s, caret.index),
+            (false, true) => textutil::prev_word_start(s, caret.index),
+            (true, true) => textutil::next_word_end(s, caret.index),
+        });
+        TextPos::new(caret.line, index)
+    }
+
+    /// Insert text in place of the selection
+    fn insert(&self, s: &str) {
+        if let Some((start, end)) = self.get_selection() {
+            self.buffer.delete(start, end);
+            self.caret.set(start);
+        }
+        let pos = self.buffer.insert(self.caret.get(), s);
+        self.move_caret(pos, false);
+    }
+
+    /// Delete the selection, or the text between the caret and the given position
+    fn delete_to(&self, pos: TextPos) {
+        let caret = self.caret.get();
+        let (start, end) = self.get_selection().unwrap_or((min(caret, pos), max(caret, pos)));
+        self.buffer.delete(start, end);
+        self.move_caret(start, false);
+    }
+
+    /// Scroll so that the caret is visible
+    fn scroll_to_caret(&self, g: &Geometry) {
+        let row = self.row_of(self.caret.get());
+        let scroll_row = self.scroll_row.get();
+        if row < scroll_row {
+            self.scroll_row.set(row);
+        }else if row >= scroll_row + g.visible_rows {
+            self.scroll_row.set(row + 1 - g.visible_rows);
+        }
+
+        if !self.wrap.get() {
+            // Keep the caret inside the clip at the right end
+            let width = g.text.width().saturating_sub(1);
+            let x = self.x_in_row(self.caret.get());
+            if x < self.scroll_x.get() {
+                self.scroll_x.set(x);
+            }else if x > self.scroll_x.get() + width {
+                self.scroll_x.set(x - width);
+            }
+        }
+    }
+
+    fn key_down(&self, g: &Geometry, keycode: Keycode, keymod: Mod) {
+        let shift = shift_pressed(keymod);
+        let ctrl = ctrl_pressed(keymod);
+        let editable = self.editable.get();
+
+        match keycode {
+            Keycode::Left | Keycode::Right => {
+                let forward = keycode == Keycode::Right;
+                match self.get_selection() {
+                    // Without Shift, the caret goes to the edge of the selection
+                    Some((start, end)) if !shift => {
+                        self.move_caret(if forward { end }else{ start }, false);
+                    },
+                    _ => {
+                        let target = self.arrow_target(forward, ctrl);
+                        self.move_caret(target, shift);
+                    },
+                }
+            },
+            Keycode::Up => self.move_rows(-1, shift),
+            Keycode::Down => self.move_rows(1, shift),
+            Keycode::PageUp => self.move_rows(-(g.visible_rows as isize), shift),
+            Keycode::PageDown => self.move_rows(g.visible_rows as isize, shift),
+            Keycode::Home => {
+                let pos = if ctrl {
+                    TextPos::default()
+                }else{
+                    let r = self.rows.borrow()[self.row_of(self.caret.get())];
+                    TextPos::new(r.line, r.start)
+                };
+                self.move_caret(pos, shift);
+            },
+            Keycode::End => {
+                let pos = if ctrl {
+                    self.buffer.end_pos()
+                }else{
+                    self.row_end(self.row_of(self.caret.get()))
+                };
+                self.move_caret(pos, shift);
+            },
+            Keycode::Backspace if editable => {
+                let target = self.arrow_target(false, ctrl);
+                self.delete_to(target);
+            },
+            Keycode::Delete if editable => {
+                let target = self.arrow_target(true, ctrl);
+                self.delete_to(target);
+            },
+            Keycode::Return | Keycode::KpEnter if editable => self.insert("\n"),
+            Keycode::A if ctrl => self.select_all(),
+            _ => (),
+        }
+    }
+
+    /// Draw the visible rows only
+    fn render_rows(&self, renderer: &mut Renderer, g: &Geometry) -> Result<(), String> {
+        let color = theme::get_theme_color();
+        let font = theme::get_default_font();
+        let line_height = line_height();
+        let text_color = if rendering_insensitive() { color.insensitive_text }else{ color.text };
+        let x = g.text.x() - if self.wrap.get() { 0 }else{ self.scroll_x.get() as i32 };
+        let selection = if self.has_focus() { self.get_selection() }else{ None };
+
+        let rows = self.rows.borrow();
+        let first = self.scroll_row.get();
+        let last = min(first + g.visible_rows + 1, rows.len());
+        for (i, r) in rows[first..last].iter().enumerate() {
+            let y = g.text.y() + (i as u32 * line_height) as i32;
+            let is_last_row = i + first + 1 >= rows.len() || rows[i + first + 1].line != r.line;
+
+            try!(self.buffer.with_line(r.line, |s| -> Result<(), String> {
+                let width_to = |index: usize| text_width(&s[r.start..index]);
+
+                if let Some((start, end)) = selection {
+                    let row_start = TextPos::new(r.line, r.start);
+                    let row_end = TextPos::new(r.line, r.end);
+                    if start <= row_end && end > row_start {
+                        let sel_start = if start > row_start { width_to(start.index) }else{ 0 };
+                        let sel_end = if end < row_end {
+                            width_to(end.index)
+                        }else{
+                            // The selection goes over the line break
+                            width_to(r.end) + if is_last_row { NEWLINE_WIDTH }else{ 0 }
+                        };
+                        if sel_end > sel_start {
+                            renderer.set_draw_color(color.selection);
+                            try!(renderer.fill_rect(Rect::new(
+                                x + sel_start as i32, y, sel_end - sel_start, line_height)));
+                        }
+                    }
+                }
+
+                let row_text = &s[r.start..r.end];
+                if !row_text.is_empty() {
+                    let surface = try!(font.render(row_text).blended(text_color).map_err(|e| e.to_string()));
+                    let texture = try!(renderer.create_texture_from_surface(surface).map_err(|e| e.to_string()));
+                    let (w, h) = try!(font.size_of(row_text).map_err(|e| e.to_string()));
+                    try!(renderer.copy(&texture, None, Some(Rect::new(x, y, w, h))));
+                }
+                Ok(())
+            }));
+        }
+
+        let caret = self.caret.get();
+        let caret_row = self.row_of(caret);
+        if self.has_focus() && self.editable.get() && caret_row >= first && caret_row < last {
+            let caret_x = x + self.x_in_row(caret) as i32;
+            let y = g.text.y() + ((caret_row - first) as u32 * line_height) as i32;
+            renderer.set_draw_color(color.text);
+            try!(renderer.draw_line(
+                Point::new(caret_x, y), Point::new(caret_x, y + line_height as i32 - 1)));
+        }
+        Ok(())
+    }
+}
+
+impl TextTrait for TextView {
+    fn set_text(&self, new_text: &str) {
+        self.buffer.set_text(new_text);
+        self.move_caret(TextPos::default(), false);
+    }
+
+    fn get_text(&self) -> String {
+        self.buffer.get_text()
+    }
+}
+
+impl WidgetTrait for TextView {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
+    fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
+        let rect = self.rect.get().expect("No rectangle allocation at rendering");
+        let g = self.geometry().unwrap();
+        if self.follow_caret.get() {
+            self.follow_caret.set(false);
+            self.scroll_to_caret(&g);
+        }
+        self.sync_bar(&g);
+
+        let color = theme::get_theme_color();
+        renderer.set_draw_color(color.light_background);
+        try!(renderer.fill_rect(rect));
+        renderer.set_draw_color(color.dark_border);
+        try!(renderer.draw_rect(rect));
+
+        // Clip inside the clip rect of the parent
+        let before_clip = renderer.clip_rect();
+        let clip = match before_clip {
+            Some(c) => c.intersection(g.text),
+            None => Some(g.text),
+        };
+        if let Some(clip) = clip {
+            renderer.set_clip_rect(Some(clip));
+            let result = self.render_rows(renderer, &g);
+            renderer.set_clip_rect(before_clip);
+            try!(result);
+        }
+
+        if g.bar.is_some() {
+            try!(self.vbar.render(renderer));
+        }
+        Ok(())
+    }
+
+    fn alloc_rect(&self, rect: Rect) {
+        self.rect.set(Some(rect));
+    }
+
+    fn event_handler(&self, event: &Event) {
+        let (rect, g) = match (self.rect.get(), self.geometry()) {
+            (Some(r), Some(g)) => (r, g),
+            _ => { return; },
+        };
+        self.sync_bar(&g);
+        let on_bar = |p: (i32, i32)| g.bar.map_or(false, |b| b.contains(p));
+
+        // The wheel is handled below for the whole view
+        if g.bar.is_some() {
+            match *event {
+                Event::MouseWheel(..) => (),
+                _ => self.vbar.event_handler(event),
+            }
+        }
+
+        match *event {
+            Event::ButtonDown(p, Mouse::Left) if rect.contains(p) => {
+                self.grab_focus();
+                if !on_bar(p) {
+                    // Dragging from here selects text
+                    self.dragging.set(true);
+                    let pos = self.pos_at(&g, p);
+                    self.move_caret(pos, false);
+                }
+            },
+            Event::ButtonDown(p, _) if !rect.contains(p) => {
+                // The frame has taken the focus
+                self.anchor.set(None);
+            },
+            Event::MouseMotion(p) if self.dragging.get() => {
+                let pos = self.pos_at(&g, p);
+                self.move_caret(pos, true);
+            },
+            Event::ButtonUp(_, Mouse::Left) => {
+                self.dragging.set(false);
+            },
+            Event::WindowFocusLost => {
+                self.cancel_input();
+            },
+            Event::MouseWheel(p, (_, dy)) if rect.contains(p) && claim_wheel() => {
+                let row = self.scroll_row.get() as i32 - dy * WHEEL_ROWS;
+                self.scroll_row.set(max(row, 0) as usize);
+                request_redraw();
+            },
+            Event::TextInput(ref s) if self.has_focus() && self.editable.get() => {
+                self.insert(s);
+            },
+            Event::KeyDown(keycode, keymod) if self.has_focus() => {
+                self.key_down(&g, keycode, keymod);
+            },
+            _ => (),
+        }
+    }
+
+    fn cancel_input(&self) {
+        self.dragging.set(false);
+        self.vbar.cancel_input();
+    }
+
+    fn preferred_width(&self) -> SizeRange {
+        SizeRange::new(PADDING * 2 + 20, NATURAL_WIDTH)
+    }
+
+    fn preferred_height_for_width(&self, _width: u32) -> SizeRange {
+        let line_height = line_height();
+        SizeRange::new(line_height + PADDING * 2, line_height * NATURAL_ROWS + PADDING * 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineEdit, TextBuffer, TextPos};
+
+    fn edit(line: usize, removed: usize, inserted: usize) -> LineEdit {
+        LineEdit{ line: line, removed: removed, inserted: inserted }
+    }
+
+    #[test]
+    fn buffer_edits() {
+        let buffer = TextBuffer::new();
+        buffer.set_text("a\nb\nc");
+        let version = buffer.version.get();
+
+        buffer.insert(TextPos::new(1, 1), "x\ny");
+        assert_eq!(buffer.edit_since(version), Some(edit(1, 1, 2)));
+        buffer.delete(TextPos::new(0, 1), TextPos::new(1, 0));
+        assert_eq!(buffer.get_text(), "abx\ny\nc");
+        // Lines "a" and "b" were replaced by "abx" and "y"
+        assert_eq!(buffer.edit_since(version), Some(edit(0, 2, 2)));
+        assert_eq!(buffer.edit_since(buffer.version.get()), None);
+    }
+
+    #[test]
+    fn edit_composition() {
+        // An edit after the first one
+        assert_eq!(edit(1, 1, 3).then(edit(5, 2, 0)), edit(1, 4, 4));
+        // An edit before the first one
+        assert_eq!(edit(4, 1, 1).then(edit(0, 1, 2)), edit(0, 5, 6));
+        // An edit inside the inserted lines
+        assert_eq!(edit(2, 1, 4).then(edit(3, 2, 1)), edit(2, 1, 3));
+    }
+
+    #[test]
+    fn forgotten_edits() {
+        let buffer = TextBuffer::new();
+        let version = buffer.version.get();
+        for _ in 0..(super::MAX_EDITS + 1) {
+            buffer.append("x");
+        }
+        assert_eq!(buffer.edit_since(version), None);
+        assert_eq!(buffer.edit_since(version + 1), Some(edit(0, 1, 1)));
+    }
+}
@@ -2,29 +2,132 @@ use std::rc::Rc;
 use std::cell::{RefCell, Cell};
 
 use prelude::*;
+use uicontext::{request_redraw, request_relayout};
+use enums::{PackType, Direction};
+use widgets::aligned_rect;
+use super::boxlayout::{BoxChild, distribute, main_axis_size};
 
 pub struct VBox {
+    common: WidgetCommon,
     rect: Cell<Option<Rect>>,
-    max_width: Cell<u32>, // Max width of child widgets
-    children: RefCell<Vec<(AnyWidget, bool)>>,
+    children: RefCell<Vec<BoxChild>>,
+    spacing: Cell<u32>,
+    homogeneous: Cell<bool>,
     box_updated: Cell<bool>,  // Positions of children is updated or not
 }
 
 impl VBox {
     pub fn new() -> Rc<VBox> {
         Rc::new(VBox{
+            common: WidgetCommon::new(),
             rect: Cell::new(None),
-            max_width: Cell::new(0),
             children: RefCell::new(Vec::new()),
+            spacing: Cell::new(0),
+            homogeneous: Cell::new(false),
             box_updated: Cell::new(false),
         })
     }
 
     pub fn pack<W: Into<AnyWidget>>(&self, w: W, expand: bool) {
+        self.pack_start(w, expand, true, 0);
+    }
+
+    /// Pack a child from the top.
+    /// If fill is false, the child keeps its requested height in the expanded space.
+    pub fn pack_start<W: Into<AnyWidget>>(&self, w: W, expand: bool, fill: bool, padding: u32) {
+        self.add_child(w.into(), expand, fill, padding, PackType::Start);
+    }
+
+    /// Pack a child from the bottom
+    pub fn pack_end<W: Into<AnyWidget>>(&self, w: W, expand: bool, fill: bool, padding: u32) {
+        self.add_child(w.into(), expand, fill, padding, PackType::End);
+    }
+
+    /// Set the space between children
+    pub fn set_spacing(&self, spacing: u32) {
+        self.spacing.set(spacing);
+        self.box_updated.set(false);
+        request_redraw();
+    }
+
+    pub fn get_spacing(&self) -> u32 {
+        self.spacing.get()
+    }
+
+    /// If homogeneous is true, all children are allocated the same height
+    pub fn set_homogeneous(&self, homogeneous: bool) {
+        self.homogeneous.set(homogeneous);
+        self.box_updated.set(false);
+        request_redraw();
+    }
+
+    pub fn get_homogeneous(&self) -> bool {
+        self.homogeneous.get()
+    }
+
+    /// Insert a child at the given position in the packing order.
+    /// The other arguments are the same as pack_start and pack_end.
+    pub fn insert_at<W: Into<AnyWidget>>(&self, index: usize, w: W, expand: bool, fill: bool, padding: u32,
+                                         pack_type: PackType) {
+        {
+            let mut children = self.children.borrow_mut();
+            let index = ::std::cmp::min(index, children.len());
+            children.insert(index, BoxChild{
+                widget: w.into(), expand: expand, fill: fill, padding: padding, pack_type: pack_type,
+            });
+        }
+        self.children_changed();
+    }
+
+    /// Remove the child. Returns false if the widget is not a child of this box.
+    pub fn remove<W: Into<AnyWidget>>(&self, w: W) -> bool {
         let w = w.into();
+        let removed = {
+            let mut children = self.children.borrow_mut();
+            if let Some(i) = children.iter().position(|c| c.widget == w) {
+                children.remove(i);
+                true
+            }else{
+                false
+            }
+        };
+
+        if removed {
+            self.children_changed();
+        }
+        removed
+    }
 
-        self.children.borrow_mut().push((w, expand));
+    /// Move the child to the given position in the packing order
+    pub fn reorder<W: Into<AnyWidget>>(&self, w: W, index: usize) {
+        let w = w.into();
+        {
+            let mut children = self.children.borrow_mut();
+            if let Some(i) = children.iter().position(|c| c.widget == w) {
+                let c = children.remove(i);
+                let index = ::std::cmp::min(index, children.len());
+                children.insert(index, c);
+            }else{
+                return;
+            }
+        }
+        self.children_changed();
+    }
+
+    /// Remove all children
+    pub fn clear(&self) {
+        self.children.borrow_mut().clear();
+        self.children_changed();
+    }
+
+    fn add_child(&self, w: AnyWidget, expand: bool, fill: bool, padding: u32, pack_type: PackType) {
+        let len = self.children.borrow().len();
+        self.insert_at(len, w, expand, fill, padding, pack_type);
+    }
+
+    fn children_changed(&self) {
         self.box_updated.set(false);
+        request_relayout();
     }
 
     fn position_update(&self) {
@@ -38,39 +141,27 @@ impl VBox {
         let box_x = box_rect.x();
         let box_y = box_rect.y();
 
-        let mut sum_requested_height = 0;
-        let mut max_width = 0;
-        let mut n_expand_widget = 0;
-        
-        for &(ref c, expand) in self.children.borrow().iter() {
-            if let Some((w, h)) = c.requested_size() {
-                sum_requested_height += h;
-                if w > max_width { max_width = w }
-            }
-            if expand { n_expand_widget += 1 }
-        }
-        
-        let expand_widget_height = if box_height < sum_requested_height || n_expand_widget == 0 {
-            0
-        }else{
-            (box_height - sum_requested_height) / n_expand_widget
-        };
+        let children = self.children.borrow();
+        let heights: Vec<SizeRange> = children.iter().map(|c| {
+            c.widget.preferred_height_for_width(box_width)
+        }).collect();
+
+        let positions = distribute(
+            &children, &heights, box_height, self.spacing.get(), self.homogeneous.get(), Direction::Vertical);
 
-        let mut y = box_y;
-        for &(ref c, expand) in self.children.borrow().iter() {
-            let requested_height = if let Some((_, h)) = c.requested_size() { h }else{ 0 };
-            let mut height = requested_height;
-            if expand { height += expand_widget_height }
-            c.alloc_rect(Rect::from((box_x, y, box_width, height)));
-            y += height as i32;
+        for (c, &(y, height)) in children.iter().zip(positions.iter()) {
+            c.widget.alloc_rect(aligned_rect(&c.widget, Rect::from((box_x, box_y + y, box_width, height))));
         }
         
         self.box_updated.set(true);
-        self.max_width.set(max_width);
     }
 }
 
 impl WidgetTrait for VBox {
+    fn common(&self) -> &WidgetCommon {
+        &self.common
+    }
+
     fn render(&self, renderer: &mut Renderer) -> Result<(), String> {
         if !self.box_updated.get() {
             self.position_update();
@@ -79,8 +170,8 @@ impl WidgetTrait for VBox {
         let before_clip = renderer.clip_rect();
         renderer.set_clip_rect(self.rect.get());
 
-        for &(ref c, _) in self.children.borrow().iter() {
-            try!(c.render(renderer));
+        for c in self.children.borrow().iter() {
+            try!(c.widget.render(renderer));
         }
 
         renderer.set_clip_rect(before_clip);
@@ -94,21 +185,32 @@ impl WidgetTrait for VBox {
     }
 
     fn event_handler(&self, event: &Event) {
-        self.with_children(|w| {
+        for w in self.children() {
             w.event_handler(event);
-        });
+        }
     }
 
-    fn requested_size(&self) -> Option<(u32, u32)> {
-        let mut max_width = 0;
+    fn cancel_input(&self) {
+        for c in self.children() {
+            c.cancel_input();
+        }
+    }
+
+    fn preferred_width(&self) -> SizeRange {
+        let mut size = SizeRange::default();
         self.with_children(|widget| {
-            if let Some((w, _)) = widget.requested_size() {
-                if max_width < w { max_width = w; }
-            }
+            size = size.max(widget.preferred_width());
         });
+        size
+    }
+
+    fn preferred_height_for_width(&self, width: u32) -> SizeRange {
+        let children = self.children.borrow();
+        let heights: Vec<SizeRange> = children.iter().map(|c| {
+            c.widget.preferred_height_for_width(width)
+        }).collect();
 
-        self.max_width.set(max_width);
-        Some((1, max_width))
+        main_axis_size(&children, &heights, self.spacing.get(), self.homogeneous.get())
     }
 }
 
@@ -116,8 +218,8 @@ impl ContainerTrait for VBox {
     fn with_children<F: FnMut(&AnyWidget)>(&self, f: F) {
         let mut f = f;
 
-        for &(ref c, _) in self.children.borrow().iter() {
-            f(&c);
+        for c in self.children.borrow().iter() {
+            f(&c.widget);
         }
     }
 }